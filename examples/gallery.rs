@@ -13,9 +13,13 @@ use bevy::DefaultPlugins;
 use bevy::render::RenderPlugin;
 use bevy_normal_material::prelude::{NormalMaterial, NormalMaterialPlugin};
 use bevy_more_shapes::torus::Torus;
-use bevy_more_shapes::{Cone, Cylinder, Grid, Polygon};
+use bevy_more_shapes::follow::FollowMode;
+use bevy_more_shapes::cylinder::Shading;
+use bevy_more_shapes::{Cable, Cone, Cylinder, ExtrudedPolygon, FollowCurve, FollowCurvePlugin, Grid, Polygon, PolygonWithHoles, Ribbon};
 use smooth_bevy_cameras::controllers::fps::{FpsCameraBundle, FpsCameraController, FpsCameraPlugin};
-use bevy_more_shapes::tube::{Curve, Tube};
+use bevy_more_shapes::curves::{Arc, ArcLengthCurve, CatmullRom, Chain, CubicBezier, Helix, LineSegment, TorusKnot, Transformed};
+use bevy_more_shapes::sweep::{Profile, Sweep};
+use bevy_more_shapes::tube::{AdaptiveSegmentation, Curve, FrameAlgorithm, FrameGizmo, KeyedRadius, Tube, TubeCap};
 
 struct WaveFunction;
 
@@ -29,28 +33,6 @@ impl Curve for WaveFunction {
     }
 }
 
-struct Knot {
-    rotation_winds: u32,
-    circle_winds: u32,
-}
-
-impl Curve for Knot {
-    fn eval_at(&self, mut t: f32) -> Vec3 {
-
-        t *= std::f32::consts::TAU * 2.0;
-        let cu = f32::cos(t);
-        let su = f32::sin(t);
-        let quop = self.circle_winds as f32 / self.rotation_winds as f32 * t;
-        let cs = f32::cos(quop);
-
-        Vec3::new(
-            (2.0 + cs) * 0.5 * cu,
-            (2.0 + cs) * su * 0.5,
-            f32::sin(quop) * 0.5,
-        )
-    }
-}
-
 // Spawns the actual gallery of shapes. Spawns a row for each type in z+ direction.
 fn spawn_shapes(
     mut commands: Commands,
@@ -87,7 +69,7 @@ fn spawn_shapes(
 
     // Default cone
     commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(Cone::default())),
+        mesh: meshes.add(Mesh::try_from(Cone::default()).unwrap()),
         material: normal_materials.add(NormalMaterial::default()),
         transform: Transform::from_xyz(0.0, 0.0, 5.0),
         ..Default::default()
@@ -95,11 +77,12 @@ fn spawn_shapes(
 
     // Big cone
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cone {
+        mesh: meshes.add(Mesh::try_from(Cone {
             radius: 0.8,
             height: 2.0,
             segments: 32,
-        })),
+            ..Default::default()
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::YELLOW_GREEN)),
         transform: Transform::from_xyz(0.0, 0.0, 7.0),
         ..Default::default()
@@ -107,11 +90,12 @@ fn spawn_shapes(
 
     // Small cone
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cone {
+        mesh: meshes.add(Mesh::try_from(Cone {
             radius: 0.8,
             height: 0.3,
             segments: 32,
-        })),
+            ..Default::default()
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::DARK_GRAY)),
         transform: Transform::from_xyz(0.0, 0.0, 9.0),
         ..Default::default()
@@ -119,15 +103,88 @@ fn spawn_shapes(
 
     // Textured cone
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cone::default())),
+        mesh: meshes.add(Mesh::try_from(Cone::default()).unwrap()),
         material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
         transform: Transform::from_xyz(0.0, 0.0, 11.0),
         ..Default::default()
     });
 
+    // Lamp shade
+    {
+        let mut mat = StandardMaterial::from(Color::BISQUE);
+        mat.cull_mode = None;
+        commands.spawn(PbrBundle {
+            mesh: meshes.add(Mesh::try_from(Cone {
+                radius: 0.7,
+                height: 0.8,
+                top_radius: 0.3,
+                top_cap: false,
+                bottom_cap: false,
+                ..Default::default()
+            }).unwrap()),
+            material: materials.add(mat),
+            transform: Transform::from_xyz(0.0, 0.0, 13.0),
+            ..Default::default()
+        });
+    }
+
+    // Cup
+    {
+        let mut mat = StandardMaterial::from(Color::ALICE_BLUE);
+        mat.cull_mode = None;
+        commands.spawn(PbrBundle {
+            mesh: meshes.add(Mesh::try_from(Cone {
+                radius: 0.3,
+                height: 0.8,
+                top_radius: 0.45,
+                top_cap: false,
+                ..Default::default()
+            }).unwrap()),
+            material: materials.add(mat),
+            transform: Transform::from_xyz(0.0, 0.0, 15.0),
+            ..Default::default()
+        });
+    }
+
+    // Low poly cone, faceted because its segments meet at a steep angle
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cone {
+            segments: 8,
+            shading: Shading::AutoSmooth(0.5),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::ORANGE)),
+        transform: Transform::from_xyz(0.0, 0.0, 17.0),
+        ..Default::default()
+    });
+
+    // Half cone
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cone {
+            radial_circumference: std::f32::consts::PI,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::TOMATO)),
+        transform: Transform::from_xyz(0.0, 0.0, 19.0),
+        ..Default::default()
+    });
+
+    // Height segmented cone
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cone {
+            height: 1.5,
+            height_segments: 6,
+            cap_segments: 3,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::SEA_GREEN)),
+        transform: Transform::from_xyz(0.0, 0.0, 21.0),
+        ..Default::default()
+    });
+
     // Textured cylinder
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cylinder::default())),
+        mesh: meshes.add(Mesh::try_from(Cylinder::default()).unwrap()),
         material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
         transform: Transform::from_xyz(2.0, 0.0, 13.0),
         ..Default::default()
@@ -135,13 +192,15 @@ fn spawn_shapes(
 
     // Tiny cylinder
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cylinder {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
             height: 1.0,
             radius_bottom: 0.5,
             radius_top: 0.5,
             radial_segments: 3,
             height_segments: 1,
-        })),
+            shading: Shading::Flat,
+            ..Default::default()
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::OLIVE)),
         transform: Transform::from_xyz(2.0, 0.0, 11.0),
         ..Default::default()
@@ -152,7 +211,7 @@ fn spawn_shapes(
         let mut mat = StandardMaterial::from(Color::CRIMSON);
         mat.cull_mode = None;
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Cylinder::default())),
+            mesh: meshes.add(Mesh::try_from(Cylinder::default()).unwrap()),
             material: materials.add(mat),
             transform: Transform::from_xyz(2.0, 0.0, 5.0),
             ..Default::default()
@@ -161,7 +220,7 @@ fn spawn_shapes(
 
     // Taller regular cylinder
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cylinder::new_regular(2.2, 0.5, 16))),
+        mesh: meshes.add(Mesh::try_from(Cylinder::new_regular(2.2, 0.5, 16)).unwrap()),
         material: materials.add(StandardMaterial::from(Color::FUCHSIA)),
         transform: Transform::from_xyz(2.0, 0.0, 7.0),
         ..Default::default()
@@ -169,13 +228,14 @@ fn spawn_shapes(
 
     // Irregular cylinder
     commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(Cylinder {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
             height: 1.0,
             radius_bottom: 0.6,
             radius_top: 0.2,
             radial_segments: 64,
             height_segments: 1,
-        })),
+            ..Default::default()
+        }).unwrap()),
         material: normal_materials.add(NormalMaterial::default()),
         transform: Transform::from_xyz(2.0, 0.0, 9.0),
         ..Default::default()
@@ -183,21 +243,107 @@ fn spawn_shapes(
 
     // Height segmented cylinder
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Cylinder {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
             height: 1.0,
             radius_bottom: 0.3,
             radius_top: 0.5,
             radial_segments: 32,
             height_segments: 5,
-        })),
+            ..Default::default()
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::SEA_GREEN)),
         transform: Transform::from_xyz(2.0, 0.0, 15.0),
         ..Default::default()
     });
 
+    // Pie slice pillar
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
+            radial_circumference: std::f32::consts::TAU * 0.8,
+            radial_offset: std::f32::consts::FRAC_PI_4,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::GOLD)),
+        transform: Transform::from_xyz(2.0, 0.0, 17.0),
+        ..Default::default()
+    });
+
+    // Partially open silo
+    {
+        let mut mat = StandardMaterial::from(Color::SILVER);
+        mat.cull_mode = None;
+        commands.spawn(PbrBundle {
+            mesh: meshes.add(Mesh::try_from(Cylinder {
+                height: 1.5,
+                radius_bottom: 0.4,
+                radius_top: 0.4,
+                radial_circumference: std::f32::consts::TAU * 0.75,
+                sector_walls: false,
+                ..Default::default()
+            }).unwrap()),
+            material: materials.add(mat),
+            transform: Transform::from_xyz(2.0, 0.0, 19.0),
+            ..Default::default()
+        });
+    }
+
+    // Pipe
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
+            height: 2.0,
+            radius_bottom: 0.3,
+            radius_top: 0.3,
+            wall_thickness: Some(0.05),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::GRAY)),
+        transform: Transform::from_xyz(2.0, 0.0, 21.0),
+        ..Default::default()
+    });
+
+    // Ring
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
+            height: 0.2,
+            radius_bottom: 0.6,
+            radius_top: 0.6,
+            radial_segments: 64,
+            wall_thickness: Some(0.15),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::GOLD)),
+        transform: Transform::from_xyz(2.0, 0.0, 23.0),
+        ..Default::default()
+    });
+
+    // Half-pipe, laid down so it opens upwards
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
+            height: 1.5,
+            radial_circumference: std::f32::consts::PI,
+            wall_thickness: Some(0.05),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::ANTIQUE_WHITE)),
+        transform: Transform::from_xyz(2.0, 0.0, 25.0).with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+        ..Default::default()
+    });
+
+    // Cap segmented cylinder
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cylinder {
+            height_segments: 5,
+            cap_segments: 5,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::SEA_GREEN)),
+        transform: Transform::from_xyz(2.0, 0.0, 27.0),
+        ..Default::default()
+    });
+
     // Single-segment grid
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Grid::default())),
+        mesh: meshes.add(Mesh::try_from(Grid::default()).unwrap()),
         material: materials.add(StandardMaterial::from(Color::SALMON)),
         transform: Transform::from_xyz(4.0, 0.0, 5.0),
         ..Default::default()
@@ -205,12 +351,12 @@ fn spawn_shapes(
 
     // Multi-segment grid
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Grid {
+        mesh: meshes.add(Mesh::try_from(Grid {
             width: 1.0,
             height: 0.6,
             width_segments: 10,
             height_segments: 6,
-        })),
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::TEAL)),
         transform: Transform::from_xyz(4.0, 0.0, 7.0),
         ..Default::default()
@@ -218,7 +364,7 @@ fn spawn_shapes(
 
     // Single-segment grid textured
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Grid::default())),
+        mesh: meshes.add(Mesh::try_from(Grid::default()).unwrap()),
         material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
         transform: Transform::from_xyz(4.0, 0.0, 9.0),
         ..Default::default()
@@ -226,7 +372,7 @@ fn spawn_shapes(
 
     // Multi-segment grid textured
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Grid::new_square(1.0, 12))),
+        mesh: meshes.add(Mesh::try_from(Grid::new_square(1.0, 12)).unwrap()),
         material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
         transform: Transform::from_xyz(4.0, 0.0, 11.0),
         ..Default::default()
@@ -266,9 +412,33 @@ fn spawn_shapes(
         ..Default::default()
     });
 
+    // Polygon with a hole
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(PolygonWithHoles::new_regular_ngon_ring(0.7, 0.4, 6)).unwrap()),
+        material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
+        transform: Transform::from_xyz(6.0, 0.0, 13.0),
+        ..Default::default()
+    });
+
+    // Extruded star with bevel
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(ExtrudedPolygon {
+            polygon: Polygon {
+                points: generate_star_shape(5, 0.7, 0.35),
+            },
+            depth: 0.3,
+            depth_segments: 1,
+            bevel_width: 0.05,
+            bevel_segments: 3,
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::GOLD)),
+        transform: Transform::from_xyz(6.0, 0.0, 15.0),
+        ..Default::default()
+    });
+
     // Simple torus
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Torus::default())),
+        mesh: meshes.add(Mesh::try_from(Torus::default()).unwrap()),
         material: materials.add(StandardMaterial::from(Color::ALICE_BLUE)),
         transform: Transform::from_xyz(8.0, 0.0, 5.0),
         ..Default::default()
@@ -276,13 +446,13 @@ fn spawn_shapes(
 
     // Low poly torus
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Torus {
+        mesh: meshes.add(Mesh::try_from(Torus {
             radius: 0.8,
             tube_radius: 0.2,
             radial_segments: 8,
             tube_segments: 5,
             ..Default::default()
-        })),
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::PINK)),
         transform: Transform::from_xyz(8.0, 0.0, 7.0),
         ..Default::default()
@@ -290,11 +460,11 @@ fn spawn_shapes(
 
     // Thick torus
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Torus {
+        mesh: meshes.add(Mesh::try_from(Torus {
             radius: 0.5,
             tube_radius: 0.3,
             ..Default::default()
-        })),
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(Color::NAVY)),
         transform: Transform::from_xyz(8.0, 0.0, 9.0),
         ..Default::default()
@@ -302,7 +472,7 @@ fn spawn_shapes(
 
     // Textured torus
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Torus::default())),
+        mesh: meshes.add(Mesh::try_from(Torus::default()).unwrap()),
         material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
         transform: Transform::from_xyz(8.0, 0.0, 11.0),
         ..Default::default()
@@ -313,11 +483,11 @@ fn spawn_shapes(
         let mut mat = StandardMaterial::from(Color::CRIMSON);
         mat.cull_mode = None;
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Torus {
+            mesh: meshes.add(Mesh::try_from(Torus {
                 radial_circumference: std::f32::consts::PI,
                 tube_circumference: std::f32::consts::TAU,
                 ..Default::default()
-            })),
+            }).unwrap()),
             material: materials.add(mat),
             transform: Transform::from_xyz(10.0, 0.0, 5.0),
             ..Default::default()
@@ -331,12 +501,12 @@ fn spawn_shapes(
         let mut flipped_transform = Transform::from_xyz(10.0, 0.0, 7.0);
         flipped_transform.rotation = Quat::from_rotation_x(std::f32::consts::PI);
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Torus {
+            mesh: meshes.add(Mesh::try_from(Torus {
                 radial_circumference: std::f32::consts::TAU,
                 tube_circumference: std::f32::consts::PI,
                 tube_offset: std::f32::consts::PI * 1.5,
                 ..Default::default()
-            })),
+            }).unwrap()),
             material: materials.add(mat),
             transform: flipped_transform,
             ..Default::default()
@@ -348,11 +518,11 @@ fn spawn_shapes(
         let mut mat = StandardMaterial::from(checkerboard_texture.clone());
         mat.cull_mode = None;
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Mesh::from(Torus {
+            mesh: meshes.add(Mesh::try_from(Torus {
                 radial_circumference: std::f32::consts::PI * 4.0/3.0,
                 tube_circumference: std::f32::consts::TAU,
                 ..Default::default()
-            }))),
+            }).unwrap()),
             material: materials.add(mat),
             transform: Transform::from_xyz(10.0, 0.0, 9.0),
             ..Default::default()
@@ -365,10 +535,10 @@ fn spawn_shapes(
         mat.cull_mode = None;
 
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Tube {
+            mesh: meshes.add(Mesh::try_from(Tube {
                 curve: Box::new(WaveFunction),
                 ..Default::default()
-            })),
+            }).unwrap()),
             material: materials.add(mat),
             transform: Transform::from_xyz(12.0, 0.0, 5.0),
             ..Default::default()
@@ -377,15 +547,17 @@ fn spawn_shapes(
 
     // Knot
     commands.spawn(PbrBundle {
-        mesh: meshes.add(Mesh::from(Tube {
-            curve: Box::new(Knot {
-                rotation_winds: 2,
-                circle_winds: 3,
+        mesh: meshes.add(Mesh::try_from(Tube {
+            curve: Box::new(TorusKnot {
+                p: 2,
+                q: 3,
+                radius: 1.0,
+                tube_radius: 0.5,
             }),
             radius: 0.1,
             length_segments: 128,
             ..Default::default()
-        })),
+        }).unwrap()),
         material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
         transform: Transform::from_xyz(12.0, 0.0, 7.0),
         ..Default::default()
@@ -397,15 +569,17 @@ fn spawn_shapes(
         mat.cull_mode = None;
 
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Tube {
-                curve: Box::new(Knot {
-                    rotation_winds: 2,
-                    circle_winds: 3,
+            mesh: meshes.add(Mesh::try_from(Tube {
+                curve: Box::new(TorusKnot {
+                    p: 2,
+                    q: 3,
+                    radius: 1.0,
+                    tube_radius: 0.5,
                 }),
                 radius: 0.0,
                 length_segments: 128,
                 ..Default::default()
-            })),
+            }).unwrap()),
             material: materials.add(mat),
             transform: Transform::from_xyz(12.0, 0.0, 9.0),
             ..Default::default()
@@ -418,18 +592,253 @@ fn spawn_shapes(
         mat.cull_mode = None;
 
         commands.spawn(PbrBundle {
-            mesh: meshes.add(Mesh::from(Tube {
+            mesh: meshes.add(Mesh::try_from(Tube {
                 radius: 0.2,
                 radial_segments: 1,
                 curve: Box::new(WaveFunction),
                 ..Default::default()
-            })),
+            }).unwrap()),
             material: materials.add(mat),
             transform: Transform::from_xyz(12.0, 0.0, 11.0),
             ..Default::default()
         });
     }
 
+    // Star swept along a knot
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Sweep {
+            curve: Box::new(TorusKnot {
+                p: 2,
+                q: 3,
+                radius: 1.0,
+                tube_radius: 0.5,
+            }),
+            profile: Profile::from(Polygon {
+                points: generate_star_shape(5, 0.08, 0.04),
+            }),
+            length_segments: 256,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::ORANGE)),
+        transform: Transform::from_xyz(12.0, 0.0, 13.0),
+        ..Default::default()
+    });
+
+    // Tapered horn
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Tube {
+            curve: Box::new(WaveFunction),
+            radius_profile: Some(Box::new(KeyedRadius::new(vec![(0.0, 0.15), (1.0, 0.0)]))),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::BEIGE)),
+        transform: Transform::from_xyz(12.0, 0.0, 15.0),
+        ..Default::default()
+    });
+
+    // Capped pipe
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Tube {
+            curve: Box::new(WaveFunction),
+            radius: 0.1,
+            start_cap: TubeCap::Flat,
+            end_cap: TubeCap::Dome(16),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
+        transform: Transform::from_xyz(12.0, 0.0, 17.0),
+        ..Default::default()
+    });
+
+    // Spring
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Tube {
+            curve: Box::new(Helix {
+                radius: 0.3,
+                height: 1.0,
+                turns: 5.0,
+            }),
+            radius: 0.03,
+            radial_segments: 16,
+            adaptive_segmentation: Some(AdaptiveSegmentation::default()),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::SILVER)),
+        transform: Transform::from_xyz(12.0, 0.0, 19.0),
+        ..Default::default()
+    });
+
+    // Bézier tube with evenly spaced segments
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Tube {
+            curve: Box::new(ArcLengthCurve::new(CubicBezier {
+                control_points: [
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Vec3::new(0.1, 0.0, 0.0),
+                    Vec3::new(0.2, 0.0, 0.0),
+                    Vec3::new(1.0, 1.0, 0.0),
+                ],
+            })),
+            radius: 0.05,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
+        transform: Transform::from_xyz(12.0, 0.0, 21.0),
+        ..Default::default()
+    });
+
+    // Road that never banks
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Sweep {
+            curve: Box::new(CatmullRom {
+                points: vec![
+                    Vec3::new(-1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 0.3, 1.0),
+                    Vec3::new(1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 0.3, -1.0),
+                ],
+                closed: true,
+            }),
+            profile: Profile::new_rectangle(0.2, 0.02),
+            length_segments: 128,
+            frame_algorithm: FrameAlgorithm::FixedUp(Vec3::Y),
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::DARK_GRAY)),
+        transform: Transform::from_xyz(12.0, 0.0, 23.0),
+        ..Default::default()
+    });
+
+    // Twisted banner, with its frames drawn next to it
+    let banner = Tube {
+        curve: Box::new(WaveFunction),
+        radius: 0.1,
+        radial_segments: 2,
+        twist: Some(Box::new(std::f32::consts::TAU)),
+        ..Default::default()
+    };
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(FrameGizmo {
+            tube: &banner,
+            axis_length: 0.05,
+        }).unwrap()),
+        material: materials.add(StandardMaterial {
+            unlit: true,
+            ..Default::default()
+        }),
+        transform: Transform::from_xyz(13.0, 0.0, 25.0),
+        ..Default::default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(banner).unwrap()),
+        material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
+        transform: Transform::from_xyz(12.0, 0.0, 25.0),
+        ..Default::default()
+    });
+
+    // Belt around a torus knot
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Ribbon {
+            curve: Box::new(TorusKnot {
+                p: 2,
+                q: 3,
+                radius: 1.0,
+                tube_radius: 0.5,
+            }),
+            width: 0.15,
+            thickness: 0.02,
+            length_segments: 256,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(checkerboard_texture.clone())),
+        transform: Transform::from_xyz(12.0, 0.0, 27.0),
+        ..Default::default()
+    });
+
+    // Rope
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Cable {
+            curve: Box::new(TorusKnot {
+                p: 2,
+                q: 3,
+                radius: 1.0,
+                tube_radius: 0.5,
+            }),
+            strand_count: 3,
+            strand_radius: 0.03,
+            winding_radius: 0.03,
+            pitch: 0.3,
+            length_segments: 512,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::BEIGE)),
+        transform: Transform::from_xyz(12.0, 0.0, 29.0),
+        ..Default::default()
+    });
+
+    // Path chained together from a line, a spring and an arc
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(Tube {
+            curve: Box::new(Chain::new(vec![
+                Box::new(LineSegment {
+                    start: Vec3::ZERO,
+                    end: Vec3::Y,
+                }),
+                Box::new(Transformed {
+                    curve: Helix {
+                        radius: 0.3,
+                        height: 1.0,
+                        turns: 3.0,
+                    },
+                    transform: Transform::from_xyz(-0.3, 0.0, 0.0),
+                }),
+                Box::new(Arc {
+                    radius: 0.5,
+                    start_angle: 0.0,
+                    end_angle: std::f32::consts::PI,
+                }),
+            ])),
+            radius: 0.02,
+            length_segments: 512,
+            radial_segments: 16,
+            ..Default::default()
+        }).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::TEAL)),
+        transform: Transform::from_xyz(12.0, 0.0, 31.0),
+        ..Default::default()
+    });
+
+    // Train on a track
+    let track = || Ribbon {
+        curve: Box::new(CatmullRom {
+            points: vec![
+                Vec3::new(-0.8, 0.0, 0.0),
+                Vec3::new(0.0, 0.2, 0.5),
+                Vec3::new(0.8, 0.0, 0.0),
+                Vec3::new(0.0, 0.2, -0.5),
+            ],
+            closed: true,
+        }),
+        width: 0.12,
+        thickness: 0.02,
+        length_segments: 128,
+        normalize: false,
+        frame_algorithm: FrameAlgorithm::FixedUp(Vec3::Y),
+        ..Default::default()
+    };
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::try_from(track()).unwrap()),
+        material: materials.add(StandardMaterial::from(Color::DARK_GRAY)),
+        transform: Transform::from_xyz(12.0, 0.0, 33.0),
+        ..Default::default()
+    }).with_children(|parent| {
+        parent.spawn(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::new(0.08, 0.06, 0.16))),
+            material: materials.add(StandardMaterial::from(Color::RED)),
+            ..Default::default()
+        }).insert(FollowCurve::along_ribbon(&track(), 0.5, FollowMode::Loop).unwrap());
+    });
+
     // Sun
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
@@ -512,8 +921,10 @@ fn spawn_info_text(mut commands: Commands, asset_server: Res<AssetServer>) {
 
 // Spawn and configure the camera.
 fn spawn_camera(mut commands: Commands) {
-    let mut controller = FpsCameraController::default();
-    controller.enabled = false; // we have a system that takes care of this, so disable it to prevent first-frame weirdness
+    let controller = FpsCameraController {
+        enabled: false, // we have a system that takes care of this, so disable it to prevent first-frame weirdness
+        ..Default::default()
+    };
 
     commands
         .spawn(Camera3dBundle::default())
@@ -537,7 +948,7 @@ fn toggle_wireframe_system(
 
 pub struct MouseLockPlugin;
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct MouseLock {
     /// If the lock is engaged the input will be grabbed and the cursor hidden.
     pub lock: bool,
@@ -568,17 +979,6 @@ impl MouseLock {
     }
 }
 
-impl Default for MouseLock {
-    fn default() -> Self {
-        MouseLock {
-            lock: false,
-            override_default_lock_system: false,
-            last_position: None,
-            last_lock: false,
-        }
-    }
-}
-
 // Determines the correct lock state based on inputs. ESC to drop focus, click on the window to regain it.
 fn automatic_lock_system(
     mut lock: ResMut<MouseLock>,
@@ -666,6 +1066,7 @@ fn main() {
         .add_plugin(WireframePlugin)
         .add_plugin(MouseLockPlugin)
         .add_plugin(NormalMaterialPlugin)
+        .add_plugin(FollowCurvePlugin)
         .add_startup_system(spawn_camera)
         .add_startup_system(spawn_shapes)
         .add_startup_system(spawn_info_text)
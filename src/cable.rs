@@ -0,0 +1,158 @@
+use std::f32::consts::TAU;
+use std::ops::Deref;
+use bevy::math::Vec3;
+use bevy::prelude::Mesh;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use crate::MeshData;
+use crate::error::{check, ShapeError};
+use crate::tube::{add_tube_on_frames, calculate_frames, normalize_frames, validate, Curve, DefaultCurve, FrameAlgorithm, FrenetSerretFrame, Tube, TubeCap};
+
+/// Several strands wound around a curve in a helix, like a rope or a twisted wire.
+/// Every strand is a tube placed on the curve's frames, they all end up in the same mesh.
+pub struct Cable {
+    pub curve: Box<dyn Curve>,
+    pub strand_count: u32,
+    pub strand_radius: f32,
+    /// The distance from the curve to the center of each strand.
+    pub winding_radius: f32,
+    /// The distance along the curve in which each strand makes a full turn. Negative values wind the other way around.
+    /// On closed curves, this is adjusted so each strand makes a whole number of turns and meets up with itself.
+    pub pitch: f32,
+    pub length_segments: u32,
+    /// The number of segments around each strand.
+    pub radial_segments: u32,
+    /// Whether the curve is recentered and scaled to fit into the unit box.
+    /// Disable this to keep the curve's coordinates, the radii and pitch are then measured in the curve's units as well.
+    pub normalize: bool,
+    /// How the strands are placed around the curve.
+    pub frame_algorithm: FrameAlgorithm,
+    /// The normal at the start of the curve, for frame algorithms that don't determine it themselves.
+    /// The first strand starts out in this direction.
+    pub initial_normal: Option<Vec3>,
+    /// Closes each strand where the curve starts.
+    pub start_cap: TubeCap,
+    /// Closes each strand where the curve ends.
+    pub end_cap: TubeCap,
+}
+
+impl Default for Cable {
+    fn default() -> Self {
+        Cable {
+            curve: Box::new(DefaultCurve), // straight line
+            strand_count: 3,
+            strand_radius: 0.02,
+            winding_radius: 0.02,
+            pitch: 0.2,
+            length_segments: 256,
+            radial_segments: 16,
+            normalize: true,
+            frame_algorithm: FrameAlgorithm::ParallelTransport,
+            initial_normal: None,
+            start_cap: TubeCap::None,
+            end_cap: TubeCap::None,
+        }
+    }
+}
+
+impl TryFrom<Cable> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(cable: Cable) -> Result<Self, Self::Error> {
+
+        check(cable.strand_count >= 1, "strand_count", "must be at least 1")?;
+        check(cable.strand_radius > 0.0, "strand_radius", "must be positive")?;
+        check(cable.winding_radius >= 0.0, "winding_radius", "must not be negative")?;
+        check(cable.pitch.is_finite() && cable.pitch != 0.0, "pitch", "must be finite and not zero")?;
+        check(cable.radial_segments >= 3, "radial_segments", "must be at least 3")?;
+
+        // Every strand is built like a tube with these settings
+        let strand_tube = Tube {
+            radius: cable.strand_radius,
+            length_segments: cable.length_segments,
+            radial_segments: cable.radial_segments,
+            normalize: false,
+            start_cap: cable.start_cap,
+            end_cap: cable.end_cap,
+            ..Default::default()
+        };
+        validate(&strand_tube)?;
+
+        let mut frames = calculate_frames(cable.curve.deref(), cable.length_segments + 1, cable.frame_algorithm, cable.initial_normal);
+        if cable.normalize {
+            normalize_frames(frames.as_mut_slice());
+        }
+
+        // The strands wind around by the distance travelled along the curve
+        let mut distances = Vec::with_capacity(frames.len());
+        let mut distance = 0.0;
+        for (idx, frame) in frames.iter().enumerate() {
+            if idx > 0 {
+                distance += frame.origin.distance(frames[idx - 1].origin);
+            }
+            distances.push(distance);
+        }
+        let closed = cable.curve.is_closed();
+        let angle_per_distance = if closed && distance > 0.0 {
+            TAU * (distance / cable.pitch).round() / distance
+        }
+        else {
+            TAU / cable.pitch
+        };
+
+        let num_vertices = cable.strand_count as usize * (cable.length_segments + 1) as usize * (cable.radial_segments + 1) as usize;
+        let num_indices = cable.strand_count as usize * cable.length_segments as usize * cable.radial_segments as usize * 6;
+        let mut mesh = MeshData::new(num_vertices, num_indices);
+
+        let ts: Vec<f32> = (0..frames.len()).map(|i| i as f32 / cable.length_segments as f32).collect();
+        for strand in 0..cable.strand_count {
+
+            // The strand's center winds around the curve on the curve's own frames, so it can't drift away from it
+            let phase = TAU * strand as f32 / cable.strand_count as f32;
+            let radials: Vec<Vec3> = frames.iter()
+                .zip(&distances)
+                .map(|(frame, distance)| {
+                    let theta = phase + angle_per_distance * distance;
+                    theta.cos() * frame.normal + theta.sin() * frame.binormal
+                })
+                .collect();
+            let centers: Vec<Vec3> = frames.iter()
+                .zip(&radials)
+                .map(|(frame, radial)| frame.origin + cable.winding_radius * *radial)
+                .collect();
+
+            // The strand's rings face along its helix. Closed curves wrap around at the seam.
+            let last = centers.len() - 1;
+            let strand_frames: Vec<FrenetSerretFrame> = (0..centers.len())
+                .map(|idx| {
+                    let (prev, next) = match idx {
+                        0 if closed => (centers[last - 1], centers[1]),
+                        0 => (centers[0], centers[1]),
+                        _ if idx == last && closed => (centers[last - 1], centers[1]),
+                        _ if idx == last => (centers[last - 1], centers[last]),
+                        _ => (centers[idx - 1], centers[idx + 1]),
+                    };
+                    let tangent = (next - prev).try_normalize().unwrap_or(frames[idx].tangent);
+
+                    // Keep the strand's normal pointing away from the curve, like the rest of its frame
+                    let normal = (radials[idx] - radials[idx].dot(tangent) * tangent).try_normalize().unwrap_or(frames[idx].normal);
+                    FrenetSerretFrame {
+                        origin: centers[idx],
+                        tangent,
+                        normal,
+                        binormal: tangent.cross(normal),
+                    }
+                })
+                .collect();
+
+            add_tube_on_frames(&mut mesh, &strand_tube, &ts, &strand_frames)?;
+        }
+
+        let mut m = Mesh::new(PrimitiveTopology::TriangleList);
+        m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
+        m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
+        m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
+        m.set_indices(Some(Indices::U32(mesh.indices)));
+        Ok(m)
+    }
+}
@@ -1,16 +1,35 @@
-use bevy::math::Vec3;
 use bevy::prelude::Vec2;
 use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_resource::PrimitiveTopology;
 use crate::MeshData;
+use crate::cylinder::{add_cap, add_sector_walls, add_side, Frustum, Shading};
+use crate::error::{check, ShapeError};
 
 // From https://github.com/ForesightMiningSoftwareCorporation/bevy_transform_gizmo/
 
 #[derive(Debug, Clone, Copy)]
 pub struct Cone {
+    /// The radius of the base.
     pub radius: f32,
     pub height: f32,
     pub segments: u32,
+    /// The number of rings the side is split into, from the base to the top.
+    pub height_segments: u32,
+    /// The number of rings the base, and the top of a frustum, are split into, from the center to the rim.
+    pub cap_segments: u32,
+    /// The radius at the top. 0 for a pointed apex, anything larger cuts the cone off into a frustum.
+    pub top_radius: f32,
+    /// Whether the top of a frustum is closed. Has no effect on pointed cones.
+    pub top_cap: bool,
+    /// Whether the base is closed.
+    pub bottom_cap: bool,
+    pub shading: Shading,
+    /// Circumference in radians around the y axis. 2pi for a full cone.
+    pub radial_circumference: f32,
+    /// The offset in radians of where on the circle the cone begins. Ignored if radial_circumference is 2pi.
+    pub radial_offset: f32,
+    /// Whether the cut faces of a partial cone are closed with flat walls. Ignored if radial_circumference is 2pi.
+    pub sector_walls: bool,
 }
 
 impl Default for Cone {
@@ -19,134 +38,86 @@ impl Default for Cone {
             radius: 0.5,
             height: 1.0,
             segments: 32,
+            height_segments: 1,
+            cap_segments: 1,
+            top_radius: 0.0,
+            top_cap: true,
+            bottom_cap: true,
+            shading: Shading::Smooth,
+            radial_circumference: std::f32::consts::TAU,
+            radial_offset: 0.0,
+            sector_walls: true,
         }
     }
 }
 
-fn add_bottom(mesh: &mut MeshData, cone: &Cone) {
+impl TryFrom<Cone> for Mesh {
 
-    let angle_step = std::f32::consts::TAU / cone.segments as f32;
-    let base_index = mesh.positions.len() as u32;
+    type Error = ShapeError;
 
-    // Center
-    let center_pos = Vec3::new(0.0, -cone.height / 2.0, 0.0);
-    mesh.positions.push(center_pos);
-    mesh.uvs.push(Vec2::new(0.5, 0.5));
-    mesh.normals.push(-Vec3::Y);
-
-    // Vertices
-    for i in 0..=cone.segments {
-
-        let theta = i as f32 * angle_step;
-        let x_unit = f32::cos(theta);
-        let z_unit = f32::sin(theta);
-
-        let pos = Vec3::new(
-            cone.radius * x_unit,
-            -cone.height / 2.0,
-            cone.radius * z_unit,
-        );
-        let uv = Vec2::new(
-            (z_unit * 0.5) + 0.5,
-            (x_unit * -0.5) + 0.5,
-        );
-
-        mesh.positions.push(pos);
-        mesh.uvs.push(uv);
-        mesh.normals.push(-Vec3::Y)
-    }
-
-    // Indices
-    for i in 0..cone.segments {
-        mesh.indices.push(base_index + i + 1);
-        mesh.indices.push(base_index + i + 2);
-        mesh.indices.push(base_index);
-    }
-}
-
-fn add_body(mesh: &mut MeshData, cone: &Cone) {
-
-    let angle_step = std::f32::consts::TAU / cone.segments as f32;
-    let base_index = mesh.positions.len() as u32;
-
-    // Add top vertices. We need to add multiple here because their normals differ
-    for i in 0..cone.segments {
-
-        let theta = i as f32 * angle_step + angle_step / 2.0;
-        let x_unit = f32::cos(theta);
-        let z_unit = f32::sin(theta);
-
-        let slope = cone.radius / cone.height;
-        let normal = Vec3::new(x_unit, slope, z_unit).normalize();
-
-        mesh.positions.push(Vec3::new(0.0, cone.height / 2.0, 0.0));
-        mesh.normals.push(normal);
-        mesh.uvs.push(Vec2::new(0.5, 0.5));
-    }
-
-    // Add bottom vertices
-    for i in 0..=cone.segments {
-
-        let theta = i as f32 * angle_step;
-        let x_unit = f32::cos(theta);
-        let z_unit = f32::sin(theta);
-
-        let slope = cone.radius / cone.height;
-        let normal = Vec3::new(x_unit, slope, z_unit).normalize();
-
-        let uv = Vec2::new(
-            (z_unit * 0.5) + 0.5,
-            (x_unit * 0.5) + 0.5,
-        );
-
-        mesh.positions.push(Vec3::new(
-            x_unit * cone.radius,
-            -cone.height / 2.0,
-            z_unit * cone.radius,
-        ));
-        mesh.normals.push(normal);
-        mesh.uvs.push(uv);
-    }
-
-    // Add indices
-    for i in 0..cone.segments {
-
-        let top = base_index + i;
-        let left = base_index + cone.segments + i;
-        let right = left + 1;
-
-        mesh.indices.push(right);
-        mesh.indices.push(left);
-        mesh.indices.push(top);
-    }
-}
-
-impl From<Cone> for Mesh {
-    fn from(cone: Cone) -> Self {
+    fn try_from(cone: Cone) -> Result<Self, Self::Error> {
 
         // Validate input parameters
-        assert!(cone.height > 0.0, "Must have positive height");
-        assert!(cone.radius > 0.0, "Must have positive radius");
-        assert!(cone.segments > 2, "Must have at least 3 subdivisions to close the surface");
+        check(cone.height > 0.0, "height", "must be positive")?;
+        check(cone.radius > 0.0, "radius", "must be positive")?;
+        check(cone.segments > 2, "segments", "must be at least 3 to close the surface")?;
+        check(cone.height_segments >= 1, "height_segments", "must be at least 1")?;
+        check(cone.cap_segments >= 1, "cap_segments", "must be at least 1")?;
+        check(cone.top_radius >= 0.0, "top_radius", "must not be negative")?;
+        check(cone.radial_circumference > 0.0 && cone.radial_circumference <= std::f32::consts::TAU, "radial_circumference", "must be in (0, 2pi]")?;
+        if cone.radial_circumference < std::f32::consts::TAU {
+            check(cone.radial_offset >= 0.0 && cone.radial_offset <= std::f32::consts::TAU, "radial_offset", "must be in [0, 2pi]")?;
+        }
 
         // code adapted from http://apparat-engine.blogspot.com/2013/04/procedural-meshes-torus.html
         // (source code at https://github.com/SEilers/Apparat)
 
-        // bottom + body
-        let n_vertices = (cone.segments + 2) + (cone.segments * 2 + 1);
-        let n_triangles = cone.segments * 2;
+        let frustum = Frustum {
+            height: cone.height,
+            radius_bottom: cone.radius,
+            radius_top: cone.top_radius,
+            radial_segments: cone.segments,
+            height_segments: cone.height_segments,
+            cap_segments: cone.cap_segments,
+            radial_circumference: cone.radial_circumference,
+            radial_offset: cone.radial_offset,
+            shading: cone.shading,
+            wall_thickness: 0.0,
+            inside: false,
+        };
+
+        // caps + body + walls
+        let n_vertices = (cone.segments + 1) * (cone.cap_segments + 1) * 2 + cone.segments * 2 * (cone.height_segments + 1) + (cone.height_segments + 1) * 4;
+        let n_triangles = cone.segments * cone.cap_segments * 4 + cone.segments * cone.height_segments * 2 + cone.height_segments * 4;
         let n_indices = n_triangles * 3;
 
         let mut mesh = MeshData::new(n_vertices as usize, n_indices as usize);
 
-        add_bottom(&mut mesh, &cone);
-        add_body(&mut mesh, &cone);
+        if cone.bottom_cap {
+            add_cap(&mut mesh, &frustum, false);
+        }
+        if cone.top_cap && cone.top_radius > 0.0 {
+            add_cap(&mut mesh, &frustum, true);
+        }
+
+        // The texture is projected from above, so the top of a frustum is further inside than the base
+        add_side(&mut mesh, &frustum, |u, v| {
+            let theta = cone.radial_offset + u * cone.radial_circumference;
+            let radius = frustum.radius_at(v) / cone.radius;
+            Vec2::new(
+                (f32::sin(theta) * radius * 0.5) + 0.5,
+                (f32::cos(theta) * radius * 0.5) + 0.5,
+            )
+        });
+        if frustum.is_sector() && cone.sector_walls {
+            add_sector_walls(&mut mesh, &frustum);
+        }
 
         let mut m = Mesh::new(PrimitiveTopology::TriangleList);
         m.set_indices(Some(Indices::U32(mesh.indices)));
         m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
         m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
         m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
-        m
+        Ok(m)
     }
 }
\ No newline at end of file
@@ -0,0 +1,576 @@
+use std::f32::consts::TAU;
+use bevy::math::Vec3;
+use bevy::prelude::Transform;
+use crate::tube::{calculate_frames, sampled_length, twist_frames, Curve, FrameAlgorithm, TwistProfile};
+
+// Maps t in [0, 1] onto one of the segments of a piecewise curve.
+// Returns the segment's index and the local parameter in [0, 1] within that segment.
+fn segment_at(t: f32, num_segments: usize) -> (usize, f32) {
+    let scaled = t.clamp(0.0, 1.0) * num_segments as f32;
+    let index = usize::min(scaled as usize, num_segments - 1);
+    (index, scaled - index as f32)
+}
+
+// Get a control point by index. Closed curves wrap around.
+// Open curves get phantom points before the first and after the last point,
+// mirrored so the curve starts and ends exactly on the control points.
+fn control_point(points: &[Vec3], index: isize, closed: bool) -> Vec3 {
+    let n = points.len() as isize;
+    if closed {
+        return points[index.rem_euclid(n) as usize];
+    }
+    if index < 0 {
+        2.0 * points[0] - points[usize::min(1, points.len() - 1)]
+    }
+    else if index >= n {
+        2.0 * points[points.len() - 1] - points[points.len().saturating_sub(2)]
+    }
+    else {
+        points[index as usize]
+    }
+}
+
+// The four control points that influence the given segment of a cubic spline
+fn segment_points(points: &[Vec3], segment: usize, closed: bool) -> [Vec3; 4] {
+    let i = segment as isize;
+    [
+        control_point(points, i - 1, closed),
+        control_point(points, i, closed),
+        control_point(points, i + 1, closed),
+        control_point(points, i + 2, closed),
+    ]
+}
+
+// The number of segments of a spline through the points
+fn num_spline_segments(points: &[Vec3], closed: bool) -> usize {
+    if closed { points.len() } else { usize::max(points.len(), 2) - 1 }
+}
+
+/// A straight line from one point to another.
+#[derive(Debug, Clone, Copy)]
+pub struct LineSegment {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl Curve for LineSegment {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        self.start.lerp(self.end, t)
+    }
+
+    fn tangent_at(&self, _: f32) -> Vec3 {
+        (self.end - self.start).normalize()
+    }
+}
+
+/// Straight lines connecting a list of points. Every line takes up the same range of t, regardless of its length.
+/// Must contain at least 2 points.
+#[derive(Debug, Clone)]
+pub struct Polyline {
+    pub points: Vec<Vec3>,
+}
+
+impl Curve for Polyline {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        match self.points.len() {
+            0 => Vec3::ZERO,
+            1 => self.points[0],
+            n => {
+                let (i, s) = segment_at(t, n - 1);
+                self.points[i].lerp(self.points[i + 1], s)
+            }
+        }
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        match self.points.len() {
+            0 | 1 => Vec3::Y,
+            n => {
+                let (i, _) = segment_at(t, n - 1);
+                (self.points[i + 1] - self.points[i]).normalize()
+            }
+        }
+    }
+}
+
+/// A circular arc in the xz plane, centered on the origin. Angles are in radians and measured from the x axis towards z.
+#[derive(Debug, Clone, Copy)]
+pub struct Arc {
+    pub radius: f32,
+    pub start_angle: f32,
+    pub end_angle: f32,
+}
+
+impl Arc {
+    /// Create a full circle.
+    pub fn new_circle(radius: f32) -> Self {
+        Self {
+            radius,
+            start_angle: 0.0,
+            end_angle: TAU,
+        }
+    }
+}
+
+impl Curve for Arc {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        let theta = self.start_angle + (self.end_angle - self.start_angle) * t;
+        Vec3::new(self.radius * theta.cos(), 0.0, self.radius * theta.sin())
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        let theta = self.start_angle + (self.end_angle - self.start_angle) * t;
+        let direction = (self.end_angle - self.start_angle).signum();
+        direction * Vec3::new(-theta.sin(), 0.0, theta.cos())
+    }
+}
+
+/// A helix winding around the y axis, starting on the x axis and rising from y = 0 to y = height.
+#[derive(Debug, Clone, Copy)]
+pub struct Helix {
+    pub radius: f32,
+    pub height: f32,
+    /// The number of full turns around the y axis. Negative values wind the other way around.
+    pub turns: f32,
+}
+
+impl Curve for Helix {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        let theta = TAU * self.turns * t;
+        Vec3::new(self.radius * theta.cos(), self.height * t, self.radius * theta.sin())
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        let omega = TAU * self.turns;
+        let theta = omega * t;
+        Vec3::new(
+            -self.radius * omega * theta.sin(),
+            self.height,
+            self.radius * omega * theta.cos(),
+        ).normalize()
+    }
+}
+
+/// A cubic Bézier curve. It starts at the first and ends at the last control point.
+#[derive(Debug, Clone, Copy)]
+pub struct CubicBezier {
+    pub control_points: [Vec3; 4],
+}
+
+impl Curve for CubicBezier {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        let [p0, p1, p2, p3] = self.control_points;
+        let u = 1.0 - t;
+        u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        let [p0, p1, p2, p3] = self.control_points;
+        let u = 1.0 - t;
+        let first = 3.0 * u * u * (p1 - p0) + 6.0 * u * t * (p2 - p1) + 3.0 * t * t * (p3 - p2);
+
+        // If a control point sits on top of an end point, the first derivative vanishes there and the second one takes over
+        let second = 6.0 * u * (p2 - 2.0 * p1 + p0) + 6.0 * t * (p3 - 2.0 * p2 + p1);
+        first.try_normalize()
+            .or_else(|| second.try_normalize())
+            .unwrap_or_else(|| (p3 - p0).normalize_or_zero())
+    }
+}
+
+/// A uniform Catmull-Rom spline. It passes through all of its control points. Must contain at least 2 points.
+#[derive(Debug, Clone)]
+pub struct CatmullRom {
+    pub points: Vec<Vec3>,
+    /// Whether the spline connects its last point back to the first one.
+    pub closed: bool,
+}
+
+impl Curve for CatmullRom {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        if self.points.is_empty() {
+            return Vec3::ZERO;
+        }
+        let (i, s) = segment_at(t, num_spline_segments(&self.points, self.closed));
+        let [p0, p1, p2, p3] = segment_points(&self.points, i, self.closed);
+        0.5 * (2.0 * p1
+            + (p2 - p0) * s
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * s * s
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * s * s * s)
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        if self.points.is_empty() {
+            return Vec3::Y;
+        }
+        let (i, s) = segment_at(t, num_spline_segments(&self.points, self.closed));
+        let [p0, p1, p2, p3] = segment_points(&self.points, i, self.closed);
+        let derivative = 0.5 * ((p2 - p0)
+            + 2.0 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * s
+            + 3.0 * (3.0 * p1 - p0 - 3.0 * p2 + p3) * s * s);
+        derivative.try_normalize().unwrap_or_else(|| (p2 - p1).normalize_or_zero())
+    }
+}
+
+/// A uniform cubic B-spline. It doesn't pass through its control points, but it's smoother than a Catmull-Rom spline.
+/// Open splines start and end on their first and last control point. Must contain at least 2 points.
+#[derive(Debug, Clone)]
+pub struct BSpline {
+    pub points: Vec<Vec3>,
+    /// Whether the spline connects its last point back to the first one.
+    pub closed: bool,
+}
+
+impl Curve for BSpline {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        if self.points.is_empty() {
+            return Vec3::ZERO;
+        }
+        let (i, s) = segment_at(t, num_spline_segments(&self.points, self.closed));
+        let [p0, p1, p2, p3] = segment_points(&self.points, i, self.closed);
+        let u = 1.0 - s;
+        (u * u * u * p0
+            + (3.0 * s * s * s - 6.0 * s * s + 4.0) * p1
+            + (-3.0 * s * s * s + 3.0 * s * s + 3.0 * s + 1.0) * p2
+            + s * s * s * p3) / 6.0
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        if self.points.is_empty() {
+            return Vec3::Y;
+        }
+        let (i, s) = segment_at(t, num_spline_segments(&self.points, self.closed));
+        let [p0, p1, p2, p3] = segment_points(&self.points, i, self.closed);
+        let u = 1.0 - s;
+        let derivative = (-3.0 * u * u * p0
+            + (9.0 * s * s - 12.0 * s) * p1
+            + (-9.0 * s * s + 6.0 * s + 3.0) * p2
+            + 3.0 * s * s * p3) / 6.0;
+        derivative.try_normalize().unwrap_or_else(|| (p2 - p1).normalize_or_zero())
+    }
+}
+
+/// A (p, q) torus knot. It winds p times around the y axis and q times through the hole of a torus lying in the xz plane.
+/// p and q must be coprime to form a single closed knot.
+#[derive(Debug, Clone, Copy)]
+pub struct TorusKnot {
+    pub p: u32,
+    pub q: u32,
+    /// The radius of the torus the knot lies on. Measured from the origin to the center line of the torus.
+    pub radius: f32,
+    /// The radius of the torus' tube the knot lies on.
+    pub tube_radius: f32,
+}
+
+impl Curve for TorusKnot {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        let phi = TAU * self.p as f32 * t;
+        let psi = TAU * self.q as f32 * t;
+        let r = self.radius + self.tube_radius * psi.cos();
+        Vec3::new(r * phi.cos(), self.tube_radius * psi.sin(), r * phi.sin())
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        let p = TAU * self.p as f32;
+        let q = TAU * self.q as f32;
+        let phi = p * t;
+        let psi = q * t;
+        let r = self.radius + self.tube_radius * psi.cos();
+        let dr = -self.tube_radius * q * psi.sin();
+        Vec3::new(
+            dr * phi.cos() - r * p * phi.sin(),
+            self.tube_radius * q * psi.cos(),
+            dr * phi.sin() + r * p * phi.cos(),
+        ).normalize()
+    }
+}
+
+/// Wraps a curve so that t is proportional to the distance travelled along it.
+/// Evenly spaced values of t then result in evenly spaced points, which keeps tube segments and their texture coordinates even.
+pub struct ArcLengthCurve<C: Curve> {
+    curve: C,
+    // The length of the curve up to evenly spaced values of the wrapped curve's t
+    lengths: Vec<f32>,
+}
+
+impl<C: Curve> ArcLengthCurve<C> {
+    /// Wrap a curve, measuring it at 1024 points.
+    pub fn new(curve: C) -> Self {
+        Self::with_samples(curve, 1024)
+    }
+
+    /// Wrap a curve, measuring it at the given number of points. More points are more accurate on sharp bends.
+    pub fn with_samples(curve: C, samples: usize) -> Self {
+        let samples = usize::max(samples, 2);
+        let mut lengths = Vec::with_capacity(samples);
+        let mut length = 0.0;
+        let mut prev = curve.eval_at(0.0);
+        lengths.push(0.0);
+        for i in 1..samples {
+            let p = curve.eval_at(i as f32 / (samples - 1) as f32);
+            length += p.distance(prev);
+            lengths.push(length);
+            prev = p;
+        }
+        Self { curve, lengths }
+    }
+
+    /// The wrapped curve.
+    pub fn inner(&self) -> &C {
+        &self.curve
+    }
+
+    /// Map a fraction of the curve's length to the wrapped curve's t.
+    pub fn curve_t(&self, t: f32) -> f32 {
+        let total = self.lengths[self.lengths.len() - 1];
+        if total <= 0.0 {
+            return t;
+        }
+
+        // Find the measured points around the target length and interpolate between them
+        let target = t.clamp(0.0, 1.0) * total;
+        let upper = self.lengths.partition_point(|length| *length < target);
+        if upper == 0 {
+            return 0.0;
+        }
+        let l0 = self.lengths[upper - 1];
+        let l1 = self.lengths[upper];
+        let s = if l1 > l0 { (target - l0) / (l1 - l0) } else { 0.0 };
+        ((upper - 1) as f32 + s) / (self.lengths.len() - 1) as f32
+    }
+}
+
+impl<C: Curve> Curve for ArcLengthCurve<C> {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        self.curve.eval_at(self.curve_t(t))
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        self.curve.tangent_at(self.curve_t(t))
+    }
+
+    fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1]
+    }
+}
+
+/// A curve moved, rotated and scaled by a transform.
+pub struct Transformed<C: Curve> {
+    pub curve: C,
+    pub transform: Transform,
+}
+
+impl<C: Curve> Curve for Transformed<C> {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        self.transform.transform_point(self.curve.eval_at(t))
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        // Directions aren't affected by the translation
+        (self.transform.rotation * (self.transform.scale * self.curve.tangent_at(t))).normalize()
+    }
+
+    fn length(&self) -> f32 {
+        // A uniform scale stretches the whole curve by the same factor, any other scale depends on its direction
+        let scale = self.transform.scale.abs();
+        if scale.max_element() - scale.min_element() <= f32::EPSILON * scale.max_element() {
+            scale.x * self.curve.length()
+        }
+        else {
+            sampled_length(self)
+        }
+    }
+}
+
+/// A curve that runs from its end to its start.
+pub struct Reversed<C: Curve> {
+    pub curve: C,
+}
+
+impl<C: Curve> Curve for Reversed<C> {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        self.curve.eval_at(1.0 - t)
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        -self.curve.tangent_at(1.0 - t)
+    }
+
+    fn length(&self) -> f32 {
+        self.curve.length()
+    }
+}
+
+/// The part of a curve between two values of its t. The range is stretched to [0, 1].
+/// If start is greater than end, the part runs backwards.
+pub struct SubCurve<C: Curve> {
+    pub curve: C,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl<C: Curve> SubCurve<C> {
+    fn curve_t(&self, t: f32) -> f32 {
+        self.start + (self.end - self.start) * t
+    }
+}
+
+impl<C: Curve> Curve for SubCurve<C> {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        self.curve.eval_at(self.curve_t(t))
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        (self.end - self.start).signum() * self.curve.tangent_at(self.curve_t(t))
+    }
+}
+
+/// Curves joined one after another. Every curve is moved to start where the previous one ends,
+/// and gets a share of t proportional to its length. Only the positions are joined, the curves aren't rotated,
+/// so there is a sharp corner at every join where the end tangent of one curve doesn't match the start tangent of the next.
+pub struct Chain {
+    curves: Vec<Box<dyn Curve>>,
+    // The value of t each curve starts at, followed by 1
+    starts: Vec<f32>,
+    // How far each curve is moved to connect to the previous one
+    offsets: Vec<Vec3>,
+    length: f32,
+}
+
+impl Chain {
+    /// Join the curves in the given order.
+    pub fn new(curves: Vec<Box<dyn Curve>>) -> Self {
+        let lengths: Vec<f32> = curves.iter().map(|curve| curve.length()).collect();
+        let length: f32 = lengths.iter().sum();
+
+        let mut starts = Vec::with_capacity(curves.len() + 1);
+        let mut offsets = Vec::with_capacity(curves.len());
+        let mut start = 0.0;
+        let mut end = curves.first().map(|curve| curve.eval_at(0.0)).unwrap_or(Vec3::ZERO);
+        for (curve, curve_length) in curves.iter().zip(&lengths) {
+            let offset = end - curve.eval_at(0.0);
+            end = curve.eval_at(1.0) + offset;
+            offsets.push(offset);
+            starts.push(start);
+            // Curves without any length still get a share, so they aren't skipped entirely
+            start += if length > 0.0 { curve_length / length } else { 1.0 / curves.len() as f32 };
+        }
+        starts.push(1.0);
+
+        Self { curves, starts, offsets, length }
+    }
+
+    // The curve at t and the local t on it
+    fn curve_at(&self, t: f32) -> (usize, f32) {
+        let index = self.starts.partition_point(|start| *start <= t).clamp(1, self.curves.len()) - 1;
+        let start = self.starts[index];
+        let end = self.starts[index + 1];
+        let s = if end > start { ((t - start) / (end - start)).clamp(0.0, 1.0) } else { 0.0 };
+        (index, s)
+    }
+}
+
+impl Curve for Chain {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        if self.curves.is_empty() {
+            return Vec3::ZERO;
+        }
+        let (i, s) = self.curve_at(t);
+        self.curves[i].eval_at(s) + self.offsets[i]
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        if self.curves.is_empty() {
+            return Vec3::Y;
+        }
+        let (i, s) = self.curve_at(t);
+        self.curves[i].tangent_at(s)
+    }
+
+    fn length(&self) -> f32 {
+        self.length
+    }
+}
+
+/// A curve shifted sideways along the normals of its frames, like a lane next to the middle of a road.
+/// Offsets larger than the radius of a bend make the curve loop back on itself there.
+pub struct Offset<C: Curve> {
+    curve: C,
+    distance: f32,
+    // The normals of the curve's frames at evenly spaced values of t
+    normals: Vec<Vec3>,
+}
+
+impl<C: Curve> Offset<C> {
+    /// Shift the curve by the given distance. The frames are calculated like for a tube with the same frame algorithm,
+    /// no initial normal and no twist, so the offset curve stays next to such a tube.
+    pub fn new(curve: C, distance: f32, frame_algorithm: FrameAlgorithm) -> Self {
+        Self::with_frames(curve, distance, frame_algorithm, None, None)
+    }
+
+    /// Shift the curve by the given distance, along frames calculated like for a tube with the same frame algorithm, initial normal and twist.
+    pub fn with_frames(curve: C, distance: f32, frame_algorithm: FrameAlgorithm, initial_normal: Option<Vec3>, twist: Option<&dyn TwistProfile>) -> Self {
+        const SAMPLES: u32 = 256;
+
+        let mut frames = calculate_frames(&curve, SAMPLES + 1, frame_algorithm, initial_normal);
+        if let Some(twist) = twist {
+            let ts: Vec<f32> = (0..=SAMPLES).map(|i| i as f32 / SAMPLES as f32).collect();
+            twist_frames(frames.as_mut_slice(), &ts, twist);
+        }
+        let normals = frames.into_iter().map(|frame| frame.normal).collect();
+        Self { curve, distance, normals }
+    }
+
+    // Interpolate between the nearest frames and make sure the normal is perpendicular to the tangent
+    fn normal_at(&self, t: f32, tangent: Vec3) -> Vec3 {
+        let (i, s) = segment_at(t, self.normals.len() - 1);
+        self.normals[i].lerp(self.normals[i + 1], s)
+            .reject_from_normalized(tangent)
+            .normalize_or_zero()
+    }
+}
+
+impl<C: Curve> Curve for Offset<C> {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        let tangent = self.curve.tangent_at(t);
+        self.curve.eval_at(t) + self.distance * self.normal_at(t, tangent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec3;
+    use bevy::prelude::Transform;
+    use crate::tube::{calculate_frames, twist_frames, Curve, FrameAlgorithm};
+    use super::{Arc, Helix, LineSegment, Offset, TorusKnot, Transformed};
+
+    #[test]
+    fn closed_curves_stay_closed_when_moved() {
+        for translation in [Vec3::ZERO, Vec3::new(-1000.0, 0.0, 0.0), Vec3::new(250.0, -3000.0, 40.0)] {
+            let circle = Transformed { curve: Arc::new_circle(1000.0), transform: Transform::from_translation(translation) };
+            assert!(circle.is_closed(), "circle moved by {translation} is open");
+            let knot = Transformed {
+                curve: TorusKnot { p: 2, q: 3, radius: 100.0, tube_radius: 40.0 },
+                transform: Transform::from_translation(translation),
+            };
+            assert!(knot.is_closed(), "knot moved by {translation} is open");
+        }
+        assert!(!LineSegment { start: Vec3::ZERO, end: Vec3::X }.is_closed());
+    }
+
+    #[test]
+    fn offset_follows_the_tube_frames() {
+        let curve = || Helix { radius: 1.0, height: 2.0, turns: 1.5 };
+        let initial_normal = Some(Vec3::new(0.3, 1.0, 0.2));
+        let twist = std::f32::consts::PI;
+        let offset = Offset::with_frames(curve(), 0.25, FrameAlgorithm::ParallelTransport, initial_normal, Some(&twist));
+
+        // Frames like those of a tube with 128 length segments
+        let mut frames = calculate_frames(&curve(), 129, FrameAlgorithm::ParallelTransport, initial_normal);
+        let ts: Vec<f32> = (0..=128).map(|i| i as f32 / 128.0).collect();
+        twist_frames(frames.as_mut_slice(), &ts, &twist);
+        for (frame, t) in frames.iter().zip(ts).step_by(16) {
+            let expected = frame.origin + 0.25 * frame.normal;
+            assert!(offset.eval_at(t).distance(expected) < 1e-3, "{} {}", offset.eval_at(t), expected);
+        }
+    }
+}
@@ -5,14 +5,50 @@ use bevy::prelude::Vec2;
 use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_resource::PrimitiveTopology;
 use crate::MeshData;
+use crate::error::{check, ShapeError};
 use crate::util::FlatTrapezeIndices;
 
+/// How the side of a cylinder or cone is shaded. The caps always meet the side at a hard edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shading {
+    /// The normals are interpolated around the side, so it appears round.
+    Smooth,
+    /// Every face around the side keeps its own normal, so the side appears faceted.
+    Flat,
+    /// Smooth if neighbouring faces meet at an angle smaller than this one (in radians), flat otherwise.
+    AutoSmooth(f32),
+}
+
+impl Shading {
+
+    // Whether two neighbouring faces whose normals are this far apart share their normals
+    fn is_smooth(self, angle: f32) -> bool {
+        match self {
+            Shading::Smooth => true,
+            Shading::Flat => false,
+            Shading::AutoSmooth(max_angle) => angle < max_angle,
+        }
+    }
+}
+
 pub struct Cylinder {
     pub height: f32,
     pub radius_bottom: f32,
     pub radius_top: f32,
     pub radial_segments: u32,
     pub height_segments: u32,
+    /// The number of rings the top and bottom discs are split into, from the center to the rim.
+    pub cap_segments: u32,
+    pub shading: Shading,
+    /// Circumference in radians around the y axis. 2pi for a full cylinder.
+    pub radial_circumference: f32,
+    /// The offset in radians of where on the circle the cylinder begins. Ignored if radial_circumference is 2pi.
+    pub radial_offset: f32,
+    /// Whether the cut faces of a partial cylinder are closed with flat walls. Ignored if radial_circumference is 2pi.
+    pub sector_walls: bool,
+    /// Makes the cylinder hollow, with an inner side this far inside the outer one. Measured along the radius.
+    /// None for a solid cylinder.
+    pub wall_thickness: Option<f32>,
 }
 
 impl Default for Cylinder {
@@ -23,6 +59,12 @@ impl Default for Cylinder {
             radius_top: 0.5,
             radial_segments: 32,
             height_segments: 1,
+            cap_segments: 1,
+            shading: Shading::Smooth,
+            radial_circumference: std::f32::consts::TAU,
+            radial_offset: 0.0,
+            sector_walls: true,
+            wall_thickness: None,
         }
     }
 }
@@ -35,165 +77,353 @@ impl Cylinder {
             radius_bottom: radius,
             radius_top: radius,
             radial_segments: subdivisions,
-            height_segments: 1,
+            ..Default::default()
         }
     }
 }
 
-fn add_top(mesh: &mut MeshData, cylinder: &Cylinder) {
+// A cylinder or cone, a straight line swept around the y axis. The top radius may be 0 for a pointed apex.
+#[derive(Clone, Copy)]
+pub(crate) struct Frustum {
+    pub height: f32,
+    pub radius_bottom: f32,
+    pub radius_top: f32,
+    pub radial_segments: u32,
+    pub height_segments: u32,
+    pub cap_segments: u32,
+    pub radial_circumference: f32,
+    pub radial_offset: f32,
+    pub shading: Shading,
+    // The distance between the outer and inner side of a hollow frustum, 0 for a solid one
+    pub wall_thickness: f32,
+    // Whether the side faces the axis, like the inner side of a hollow frustum
+    pub inside: bool,
+}
 
-    let angle_step = std::f32::consts::TAU / cylinder.radial_segments as f32;
-    let base_index = mesh.positions.len() as u32;
+impl Frustum {
 
-    // Center
-    let center_pos = Vec3::new(0.0, cylinder.height / 2.0, 0.0);
-    mesh.positions.push(center_pos);
-    mesh.uvs.push(Vec2::new(0.5, 0.5));
-    mesh.normals.push(Vec3::Y);
+    // The angle of the i-th edge around the y axis
+    fn theta(&self, i: u32) -> f32 {
+        self.radial_offset + i as f32 * self.radial_circumference / self.radial_segments as f32
+    }
 
-    // Vertices
-    for i in 0..=cylinder.radial_segments {
+    pub fn radius_at(&self, height_percent: f32) -> f32 {
+        (1.0 - height_percent) * self.radius_bottom + height_percent * self.radius_top
+    }
 
-        let theta = i as f32 * angle_step;
-        let x_unit = f32::cos(theta);
-        let z_unit = f32::sin(theta);
+    fn inner_radius_at(&self, height_percent: f32) -> f32 {
+        if self.wall_thickness > 0.0 { self.radius_at(height_percent) - self.wall_thickness } else { 0.0 }
+    }
 
-        let pos = Vec3::new(
-            cylinder.radius_top * x_unit,
-            cylinder.height / 2.0,
-            cylinder.radius_top * z_unit,
-        );
-        let uv = Vec2::new(
-            (z_unit * 0.5) + 0.5,
-            (x_unit * 0.5) + 0.5,
-        );
+    // The inner side of a hollow frustum
+    pub fn inner(&self) -> Frustum {
+        Frustum {
+            radius_bottom: self.radius_bottom - self.wall_thickness,
+            radius_top: self.radius_top - self.wall_thickness,
+            wall_thickness: 0.0,
+            inside: !self.inside,
+            ..*self
+        }
+    }
 
-        mesh.positions.push(pos);
-        mesh.uvs.push(uv);
-        mesh.normals.push(Vec3::Y)
+    fn is_pointed(&self) -> bool {
+        self.radius_top == 0.0
     }
 
-    // Indices
-    for i in 0..cylinder.radial_segments {
-        mesh.indices.push(base_index);
-        mesh.indices.push(base_index + i + 2);
-        mesh.indices.push(base_index + i + 1);
+    // Whether the frustum is cut open, leaving faces for sector walls
+    pub fn is_sector(&self) -> bool {
+        self.radial_circumference < std::f32::consts::TAU
+    }
+
+    // The normal of the round surface the side approximates
+    fn smooth_normal(&self, theta: f32) -> Vec3 {
+        let slope = (self.radius_bottom - self.radius_top) / self.height;
+        Vec3::new(f32::cos(theta), slope, f32::sin(theta)).normalize()
+    }
+
+    // The normal of the flat face between two angles. The face is closer to the axis than the round surface,
+    // which makes it a bit steeper.
+    fn flat_normal(&self, theta_start: f32, theta_end: f32) -> Vec3 {
+        let half_angle = (theta_end - theta_start) / 2.0;
+        let theta = theta_start + half_angle;
+        let slope = (self.radius_bottom - self.radius_top) * f32::cos(half_angle) / self.height;
+        Vec3::new(f32::cos(theta), slope, f32::sin(theta)).normalize()
     }
 }
 
-fn add_bottom(mesh: &mut MeshData, cylinder: &Cylinder) {
+// Add the disc closing the top or bottom of a cylinder or cone. Hollow frustums are closed with a ring instead.
+pub(crate) fn add_cap(mesh: &mut MeshData, frustum: &Frustum, top: bool) {
 
-    let angle_step = std::f32::consts::TAU / cylinder.radial_segments as f32;
     let base_index = mesh.positions.len() as u32;
+    let (y, height_percent, normal, uv_flip) = if top {
+        (frustum.height / 2.0, 1.0, Vec3::Y, 1.0)
+    }
+    else {
+        (-frustum.height / 2.0, 0.0, -Vec3::Y, -1.0)
+    };
+    let radius = frustum.radius_at(height_percent);
+    let hole_radius = frustum.inner_radius_at(height_percent);
 
     // Center
-    let center_pos = Vec3::new(0.0, -cylinder.height / 2.0, 0.0);
-    mesh.positions.push(center_pos);
-    mesh.uvs.push(Vec2::new(0.5, 0.5));
-    mesh.normals.push(-Vec3::Y);
+    if hole_radius == 0.0 {
+        mesh.positions.push(Vec3::new(0.0, y, 0.0));
+        mesh.uvs.push(Vec2::new(0.5, 0.5));
+        mesh.normals.push(normal);
+    }
+    let rings_base_index = mesh.positions.len() as u32;
+
+    // Vertices, ring by ring from the inside out. Without a hole, the innermost ring is the center.
+    let first_ring = if hole_radius > 0.0 { 0 } else { 1 };
+    let rings: Vec<f32> = (first_ring..=frustum.cap_segments).map(|ring| {
+        let ring_percent = ring as f32 / frustum.cap_segments as f32;
+        (1.0 - ring_percent) * hole_radius + ring_percent * radius
+    }).collect();
+    for ring_radius in &rings {
+        for i in 0..=frustum.radial_segments {
+
+            let theta = frustum.theta(i);
+            let x_unit = f32::cos(theta);
+            let z_unit = f32::sin(theta);
+
+            let pos = Vec3::new(ring_radius * x_unit, y, ring_radius * z_unit);
+            let uv_radius = ring_radius / radius;
+            let uv = Vec2::new(
+                (z_unit * uv_radius * 0.5) + 0.5,
+                (x_unit * uv_radius * 0.5 * uv_flip) + 0.5,
+            );
 
-    // Vertices
-    for i in 0..=cylinder.radial_segments {
+            mesh.positions.push(pos);
+            mesh.uvs.push(uv);
+            mesh.normals.push(normal)
+        }
+    }
 
-        let theta = i as f32 * angle_step;
+    // Indices
+    let ring_size = frustum.radial_segments + 1;
+    let mut push_triangle = |a: u32, b: u32, c: u32| {
+        if top {
+            mesh.indices.extend([a, b, c]);
+        }
+        else {
+            mesh.indices.extend([c, b, a]);
+        }
+    };
+    for i in 0..frustum.radial_segments {
+        if hole_radius == 0.0 {
+            push_triangle(base_index, rings_base_index + i + 1, rings_base_index + i);
+        }
+        for ring in 1..rings.len() as u32 {
+            let inner = rings_base_index + (ring - 1) * ring_size + i;
+            let outer = rings_base_index + ring * ring_size + i;
+            push_triangle(inner, outer + 1, outer);
+            push_triangle(inner, inner + 1, outer + 1);
+        }
+    }
+}
+
+// Add the side of a cylinder or cone. The texture coordinates are looked up by the fraction of the way around
+// and the fraction of the way up.
+pub(crate) fn add_side(mesh: &mut MeshData, frustum: &Frustum, uv: impl Fn(f32, f32) -> Vec2) {
+
+    let base_index = mesh.positions.len() as u32;
+    let pointed = frustum.is_pointed();
+
+    // All faces meet their neighbours at the same angle, so this decides for the whole side
+    let neighbour_angle = frustum.flat_normal(frustum.theta(0), frustum.theta(1)).angle_between(frustum.flat_normal(frustum.theta(1), frustum.theta(2)));
+    let smooth = frustum.shading.is_smooth(neighbour_angle);
+
+    // The apex is added separately, it needs one vertex per face to be shaded properly
+    let rows = if pointed { frustum.height_segments } else { frustum.height_segments + 1 };
+
+    // Columns of vertices from bottom to top. Flat faces don't share their columns with their neighbours.
+    let facing = if frustum.inside { -1.0 } else { 1.0 };
+    let columns: Vec<(u32, Vec3)> = if smooth {
+        (0..=frustum.radial_segments).map(|i| (i, facing * frustum.smooth_normal(frustum.theta(i)))).collect()
+    }
+    else {
+        (0..frustum.radial_segments).flat_map(|i| {
+            let normal = facing * frustum.flat_normal(frustum.theta(i), frustum.theta(i + 1));
+            [(i, normal), (i + 1, normal)]
+        }).collect()
+    };
+
+    for (i, normal) in &columns {
+
+        let theta = frustum.theta(*i);
         let x_unit = f32::cos(theta);
         let z_unit = f32::sin(theta);
 
-        let pos = Vec3::new(
-            cylinder.radius_bottom * x_unit,
-            -cylinder.height / 2.0,
-            cylinder.radius_bottom * z_unit,
-        );
-        let uv = Vec2::new(
-            (z_unit * 0.5) + 0.5,
-            (x_unit * -0.5) + 0.5,
-        );
+        for h in 0..rows {
+            let height_percent = h as f32 / frustum.height_segments as f32;
+            let y = height_percent * frustum.height - frustum.height / 2.0;
+            let radius = frustum.radius_at(height_percent);
+
+            mesh.positions.push(Vec3::new(x_unit * radius, y, z_unit * radius));
+            mesh.normals.push(*normal);
+            mesh.uvs.push(uv(*i as f32 / frustum.radial_segments as f32, height_percent));
+        }
+    }
 
-        mesh.positions.push(pos);
-        mesh.uvs.push(uv);
-        mesh.normals.push(-Vec3::Y)
+    let apex_base_index = mesh.positions.len() as u32;
+    if pointed {
+        for i in 0..frustum.radial_segments {
+            let normal = facing * if smooth {
+                frustum.smooth_normal((frustum.theta(i) + frustum.theta(i + 1)) / 2.0)
+            }
+            else {
+                frustum.flat_normal(frustum.theta(i), frustum.theta(i + 1))
+            };
+
+            mesh.positions.push(Vec3::new(0.0, frustum.height / 2.0, 0.0));
+            mesh.normals.push(normal);
+            mesh.uvs.push(uv((i as f32 + 0.5) / frustum.radial_segments as f32, 1.0));
+        }
     }
 
     // Indices
-    for i in 0..cylinder.radial_segments {
-        mesh.indices.push(base_index + i + 1);
-        mesh.indices.push(base_index + i + 2);
-        mesh.indices.push(base_index);
+    for i in 0..frustum.radial_segments {
+
+        let (mut left_column, mut right_column) = if smooth { (i, i + 1) } else { (2 * i, 2 * i + 1) };
+
+        // Seen from the axis, left and right are swapped
+        if frustum.inside {
+            std::mem::swap(&mut left_column, &mut right_column);
+        }
+        let left = base_index + left_column * rows;
+        let right = base_index + right_column * rows;
+
+        for h in 0..(rows - 1) {
+            let indices = FlatTrapezeIndices {
+                lower_left: left + h,
+                upper_left: left + h + 1,
+                lower_right: right + h,
+                upper_right: right + h + 1,
+            };
+            indices.generate_triangles(&mut mesh.indices);
+        }
+
+        if pointed {
+            mesh.indices.push(right + rows - 1);
+            mesh.indices.push(left + rows - 1);
+            mesh.indices.push(apex_base_index + i);
+        }
     }
 }
 
-fn add_body(mesh: &mut MeshData, cylinder: &Cylinder) {
+// Add the flat walls closing the cut faces of a partial cylinder or cone.
+// They reach from the axis, or the inner side of a hollow frustum, to the outer side.
+pub(crate) fn add_sector_walls(mesh: &mut MeshData, frustum: &Frustum) {
 
-    let angle_step = std::f32::consts::TAU / cylinder.radial_segments as f32;
-    let base_index = mesh.positions.len() as u32;
+    let max_radius = f32::max(frustum.radius_bottom, frustum.radius_top);
 
-    // Vertices
-    for i in 0..=cylinder.radial_segments {
+    for start in [true, false] {
 
-        let theta = angle_step * i as f32;
+        let base_index = mesh.positions.len() as u32;
+        let theta = if start { frustum.theta(0) } else { frustum.theta(frustum.radial_segments) };
         let x_unit = f32::cos(theta);
         let z_unit = f32::sin(theta);
 
-        // Calculate normal of this segment, it's a straight line so all normals are the same
-        let slope = (cylinder.radius_bottom - cylinder.radius_top) / cylinder.height;
-        let normal = Vec3::new(x_unit, slope, z_unit).normalize();
+        // The walls face away from the rest of the shape
+        let normal = if start { Vec3::new(z_unit, 0.0, -x_unit) } else { Vec3::new(-z_unit, 0.0, x_unit) };
 
-        for h in 0..=cylinder.height_segments {
-            let height_percent = h as f32 / cylinder.height_segments as f32;
-            let y = height_percent * cylinder.height - cylinder.height / 2.0;
-            let radius = (1.0 - height_percent) * cylinder.radius_bottom + height_percent * cylinder.radius_top;
+        // Vertices, one pair per row
+        for h in 0..=frustum.height_segments {
+            let height_percent = h as f32 / frustum.height_segments as f32;
+            let y = height_percent * frustum.height - frustum.height / 2.0;
+            let radius = frustum.radius_at(height_percent);
+            let inner_radius = frustum.inner_radius_at(height_percent);
 
-            let pos = Vec3::new(x_unit * radius, y, z_unit * radius);
-            let uv = Vec2::new(i as f32 / cylinder.radial_segments as f32, height_percent);
+            mesh.positions.push(Vec3::new(x_unit * inner_radius, y, z_unit * inner_radius));
+            mesh.normals.push(normal);
+            mesh.uvs.push(Vec2::new(inner_radius / max_radius, height_percent));
 
-            mesh.positions.push(pos);
+            mesh.positions.push(Vec3::new(x_unit * radius, y, z_unit * radius));
             mesh.normals.push(normal);
-            mesh.uvs.push(uv);
+            mesh.uvs.push(Vec2::new(radius / max_radius, height_percent));
         }
-    }
 
-    // Indices
-    for i in 0..cylinder.radial_segments {
-        for h in 0..cylinder.height_segments {
-            let segment_base = base_index + (i * (cylinder.height_segments + 1)) + h;
+        // Indices
+        for h in 0..frustum.height_segments {
+            let inner = base_index + 2 * h;
+            let outer = inner + 1;
+            let (left, right) = if start { (inner, outer) } else { (outer, inner) };
             let indices = FlatTrapezeIndices {
-                lower_left: segment_base,
-                upper_left: segment_base + 1,
-                lower_right: segment_base + cylinder.height_segments + 1,
-                upper_right: segment_base + cylinder.height_segments + 2,
+                lower_left: left,
+                upper_left: left + 2,
+                lower_right: right,
+                upper_right: right + 2,
             };
-            indices.generate_triangles(&mut mesh.indices);
+
+            // The last row of a pointed cone collapses into the axis, only one triangle is left
+            if frustum.is_pointed() && h == frustum.height_segments - 1 {
+                mesh.indices.push(indices.upper_right);
+                mesh.indices.push(indices.lower_right);
+                mesh.indices.push(indices.lower_left);
+            }
+            else {
+                indices.generate_triangles(&mut mesh.indices);
+            }
         }
     }
 }
 
-impl From<Cylinder> for Mesh {
-    fn from(cylinder: Cylinder) -> Self {
+impl TryFrom<Cylinder> for Mesh {
+
+    type Error = ShapeError;
 
-        // Input parameter validation
-        assert_ne!(cylinder.radius_top, 0.0, "Radius must not be 0. Use a cone instead.");
-        assert_ne!(cylinder.radius_bottom, 0.0, "Radius must not be 0. Use a cone instead.");
-        assert!(cylinder.radius_bottom > 0.0, "Must have positive radius.");
-        assert!(cylinder.radius_top > 0.0, "Must have positive radius.");
-        assert!(cylinder.radial_segments > 2, "Must have at least 3 subdivisions to close the surface.");
-        assert!(cylinder.height_segments >= 1, "Must have at least one height segment.");
-        assert!(cylinder.height > 0.0, "Must have positive height");
+    fn try_from(cylinder: Cylinder) -> Result<Self, Self::Error> {
 
-        let num_vertices = (cylinder.radial_segments + 1) * (cylinder.height_segments + 3) + 2;
-        // top&bottom + body
-        let num_indices = cylinder.radial_segments * 3 * 2 + cylinder.radial_segments * cylinder.height_segments * 6;
+        // Input parameter validation. A radius of 0 is not allowed, use a cone instead.
+        check(cylinder.radius_bottom > 0.0, "radius_bottom", "must be positive")?;
+        check(cylinder.radius_top > 0.0, "radius_top", "must be positive")?;
+        check(cylinder.radial_segments > 2, "radial_segments", "must be at least 3 to close the surface")?;
+        check(cylinder.height_segments >= 1, "height_segments", "must be at least 1")?;
+        check(cylinder.cap_segments >= 1, "cap_segments", "must be at least 1")?;
+        check(cylinder.height > 0.0, "height", "must be positive")?;
+        check(cylinder.radial_circumference > 0.0 && cylinder.radial_circumference <= std::f32::consts::TAU, "radial_circumference", "must be in (0, 2pi]")?;
+        if cylinder.radial_circumference < std::f32::consts::TAU {
+            check(cylinder.radial_offset >= 0.0 && cylinder.radial_offset <= std::f32::consts::TAU, "radial_offset", "must be in [0, 2pi]")?;
+        }
+        if let Some(wall_thickness) = cylinder.wall_thickness {
+            check(wall_thickness > 0.0, "wall_thickness", "must be positive")?;
+            check(wall_thickness < cylinder.radius_bottom && wall_thickness < cylinder.radius_top, "wall_thickness", "must be smaller than both radii")?;
+        }
+
+        let frustum = Frustum {
+            height: cylinder.height,
+            radius_bottom: cylinder.radius_bottom,
+            radius_top: cylinder.radius_top,
+            radial_segments: cylinder.radial_segments,
+            height_segments: cylinder.height_segments,
+            cap_segments: cylinder.cap_segments,
+            radial_circumference: cylinder.radial_circumference,
+            radial_offset: cylinder.radial_offset,
+            shading: cylinder.shading,
+            wall_thickness: cylinder.wall_thickness.unwrap_or(0.0),
+            inside: false,
+        };
+
+        // top&bottom + outer&inner body + walls
+        let num_vertices = (cylinder.radial_segments + 1) * (cylinder.cap_segments + 1) * 2 + cylinder.radial_segments * 4 * (cylinder.height_segments + 1) + (cylinder.height_segments + 1) * 4;
+        let num_indices = cylinder.radial_segments * cylinder.cap_segments * 6 * 2 + cylinder.radial_segments * cylinder.height_segments * 12 + cylinder.height_segments * 12;
 
         let mut mesh = MeshData::new(num_vertices as usize, num_indices as usize);
 
-        add_top(&mut mesh, &cylinder);
-        add_bottom(&mut mesh, &cylinder);
-        add_body(&mut mesh, &cylinder);
+        add_cap(&mut mesh, &frustum, true);
+        add_cap(&mut mesh, &frustum, false);
+        add_side(&mut mesh, &frustum, Vec2::new);
+        if frustum.wall_thickness > 0.0 {
+            add_side(&mut mesh, &frustum.inner(), Vec2::new);
+        }
+        if frustum.is_sector() && cylinder.sector_walls {
+            add_sector_walls(&mut mesh, &frustum);
+        }
 
         let mut m = Mesh::new(PrimitiveTopology::TriangleList);
         m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
         m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
         m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
         m.set_indices(Some(Indices::U32(mesh.indices)));
-        m
+        Ok(m)
     }
 }
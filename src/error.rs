@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Error returned when a shape cannot be turned into a mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeError {
+    /// A parameter of the shape is outside of its valid range.
+    InvalidParameter {
+        /// The name of the offending field.
+        field: &'static str,
+        /// The constraint the field violates.
+        constraint: &'static str,
+    },
+    /// The input points of a polygon can't be triangulated, or those of a sweep's profile can't be swept.
+    InvalidPolygon(PolygonError),
+}
+
+/// Describes why a polygon can't be triangulated. Sweep profiles are checked for degenerate edges and duplicate points as well.
+/// Points are referred to by their index in the polygon's points.
+/// Edge i connects point i with point i + 1, the last edge connects the last point with the first one.
+/// For polygons with holes, the points of the outline come first, followed by the points of each hole in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolygonError {
+    /// The polygon has fewer than 3 points.
+    NotEnoughPoints(usize),
+    /// A point has a NaN or infinite coordinate.
+    NonFinitePoint(usize),
+    /// Two consecutive points are identical, so the edge between them has no length.
+    DegenerateEdge(usize),
+    /// Two points that aren't connected by an edge are identical.
+    DuplicatePoint {
+        first: usize,
+        second: usize,
+    },
+    /// Two edges cross, touch or overlap each other.
+    SelfIntersection {
+        first_edge: usize,
+        second_edge: usize,
+    },
+    /// The hole with this index is outside of the outline or inside of another hole.
+    MisplacedHole(usize),
+    /// The triangulation library rejected the input for a reason not covered by the other variants.
+    Triangulation(String),
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::InvalidParameter { field, constraint } => write!(f, "Invalid parameter: {} {}", field, constraint),
+            ShapeError::InvalidPolygon(e) => write!(f, "Invalid polygon input: {}", e),
+        }
+    }
+}
+
+impl Error for ShapeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShapeError::InvalidParameter { .. } => None,
+            ShapeError::InvalidPolygon(e) => Some(e),
+        }
+    }
+}
+
+impl From<PolygonError> for ShapeError {
+    fn from(value: PolygonError) -> Self {
+        ShapeError::InvalidPolygon(value)
+    }
+}
+
+impl Display for PolygonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolygonError::NotEnoughPoints(n) => write!(f, "Polygon has {} points but needs at least 3", n),
+            PolygonError::NonFinitePoint(i) => write!(f, "Point {} is not finite", i),
+            PolygonError::DegenerateEdge(i) => write!(f, "Edge {} has zero length", i),
+            PolygonError::DuplicatePoint { first, second } => write!(f, "Points {} and {} are identical", first, second),
+            PolygonError::SelfIntersection { first_edge, second_edge } => write!(f, "Edges {} and {} intersect", first_edge, second_edge),
+            PolygonError::MisplacedHole(i) => write!(f, "Hole {} is not inside the outline or lies within another hole", i),
+            PolygonError::Triangulation(msg) => write!(f, "Failed to triangulate: {}", msg),
+        }
+    }
+}
+
+impl Error for PolygonError { }
+
+// Shorthand used by the shapes to validate their parameters.
+pub(crate) fn check(condition: bool, field: &'static str, constraint: &'static str) -> Result<(), ShapeError> {
+    if condition {
+        Ok(())
+    }
+    else {
+        Err(ShapeError::InvalidParameter { field, constraint })
+    }
+}
@@ -0,0 +1,312 @@
+use std::f32::consts::FRAC_PI_2;
+use bevy::math::{Vec2, Vec3};
+use bevy::prelude::Mesh;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use crate::MeshData;
+use crate::error::{check, ShapeError};
+use crate::polygon::{bounding_rect_for_points, triangulate_rings, Polygon};
+use crate::util::FlatTrapezeIndices;
+
+/// A polygon extruded along the y axis into a solid prism.
+pub struct ExtrudedPolygon {
+    /// The outline of the prism. It lies in the xz plane, just like the flat polygon would.
+    pub polygon: Polygon,
+    /// The extent along the y axis, including the bevels.
+    pub depth: f32,
+    /// The number of segments the side walls are split into along the y axis. The bevels are not included.
+    pub depth_segments: u32,
+    /// How far the bevel reaches into the caps and down the side walls. 0 for sharp edges.
+    /// Must be small enough for the shrunken caps not to intersect themselves.
+    pub bevel_width: f32,
+    /// The number of segments that make up the rounded profile of each bevel. 1 for a chamfer.
+    /// Must be at least 1 if there is a bevel.
+    pub bevel_segments: u32,
+}
+
+impl ExtrudedPolygon {
+    /// Extrude a polygon without any bevel.
+    pub fn new(polygon: Polygon, depth: f32) -> Self {
+        Self {
+            polygon,
+            depth,
+            depth_segments: 1,
+            bevel_width: 0.0,
+            bevel_segments: 0,
+        }
+    }
+}
+
+// A point on the side profile of the prism. Every edge of the outline is swept along this profile.
+struct ProfilePoint {
+    // How far the outline is moved inwards
+    inset: f32,
+    y: f32,
+    // x points away from the outline, y along the y axis
+    normal: Vec2,
+}
+
+// Build the side profile from the bottom cap to the top cap
+fn build_profile(extrusion: &ExtrudedPolygon, bevel_width: f32) -> Vec<ProfilePoint> {
+
+    let half_depth = extrusion.depth / 2.0;
+    let bevel_segments = if bevel_width > 0.0 { extrusion.bevel_segments } else { 0 };
+    let mut profile = Vec::with_capacity((extrusion.depth_segments + 2 * bevel_segments + 1) as usize);
+
+    // Bottom bevel, from the edge of the cap up to the wall
+    for i in (1..=bevel_segments).rev() {
+        let phi = i as f32 / bevel_segments as f32 * FRAC_PI_2;
+        profile.push(ProfilePoint {
+            inset: bevel_width * (1.0 - phi.cos()),
+            y: -half_depth + bevel_width - bevel_width * phi.sin(),
+            normal: Vec2::new(phi.cos(), -phi.sin()),
+        });
+    }
+
+    // Wall
+    let wall_height = extrusion.depth - 2.0 * bevel_width;
+    for i in 0..=extrusion.depth_segments {
+        profile.push(ProfilePoint {
+            inset: 0.0,
+            y: -half_depth + bevel_width + wall_height * i as f32 / extrusion.depth_segments as f32,
+            normal: Vec2::X,
+        });
+    }
+
+    // Top bevel, from the wall up to the edge of the cap
+    for i in 1..=bevel_segments {
+        let phi = i as f32 / bevel_segments as f32 * FRAC_PI_2;
+        profile.push(ProfilePoint {
+            inset: bevel_width * (1.0 - phi.cos()),
+            y: half_depth - bevel_width + bevel_width * phi.sin(),
+            normal: Vec2::new(phi.cos(), phi.sin()),
+        });
+    }
+
+    profile
+}
+
+// The outward normal of the edge starting at point i. The points must be in counter-clockwise order.
+fn edge_normal(points: &[Vec2], i: usize) -> Vec2 {
+    let edge = points[(i + 1) % points.len()] - points[i];
+    Vec2::new(edge.y, -edge.x).normalize()
+}
+
+// Corners sharper than this can't be bevelled, their points would have to move out towards infinity
+const MAX_MITER_LENGTH: f32 = 1000.0;
+
+// For every point, the direction it has to move in to keep all adjacent edges at the same distance.
+// Moving a point by -miter * d moves its edges inwards by d. Corners that fold back onto themselves have no miter.
+fn miters(points: &[Vec2]) -> Vec<Vec2> {
+    (0..points.len())
+        .map(|i| {
+            let prev = edge_normal(points, (i + points.len() - 1) % points.len());
+            let next = edge_normal(points, i);
+            (prev + next) / (1.0 + prev.dot(next))
+        })
+        .collect()
+}
+
+// The caps are the outline shrunken by the bevel, the triangles index into those points
+fn add_caps(mesh: &mut MeshData, cap_points: &[Vec2], triangles: &[u32], extrusion: &ExtrudedPolygon) {
+
+    let domain = bounding_rect_for_points(cap_points.iter());
+
+    for (y, normal) in [(extrusion.depth / 2.0, Vec3::Y), (-extrusion.depth / 2.0, -Vec3::Y)] {
+
+        let base_index = mesh.positions.len() as u32;
+
+        for &p in cap_points {
+            mesh.positions.push(Vec3::new(p.x, y, p.y));
+            mesh.normals.push(normal);
+            mesh.uvs.push((p - domain.min) / (domain.max - domain.min));
+        }
+
+        // The triangulation faces y+, so the bottom needs the reverse winding
+        if normal == Vec3::Y {
+            mesh.indices.extend(triangles.iter().map(|i| base_index + i));
+        }
+        else {
+            mesh.indices.extend(triangles.chunks(3).flat_map(|t| [t[2], t[1], t[0]]).map(|i| base_index + i));
+        }
+    }
+}
+
+fn add_walls(mesh: &mut MeshData, points: &[Vec2], miters: &[Vec2], profile: &[ProfilePoint]) {
+
+    // The texture wraps around the outline once and covers the profile from bottom to top
+    let perimeter: f32 = (0..points.len()).map(|i| points[i].distance(points[(i + 1) % points.len()])).sum();
+    let mut profile_lengths = Vec::with_capacity(profile.len());
+    let mut profile_length = 0.0;
+    for (idx, point) in profile.iter().enumerate() {
+        if idx > 0 {
+            let prev = &profile[idx - 1];
+            profile_length += Vec2::new(point.inset - prev.inset, point.y - prev.y).length();
+        }
+        profile_lengths.push(profile_length);
+    }
+
+    // Every edge gets its own vertices, so the corners between them are hard
+    let mut arc_length = 0.0;
+    for i in 0..points.len() {
+
+        let j = (i + 1) % points.len();
+        let normal = edge_normal(points, i);
+        let edge_length = points[i].distance(points[j]);
+        let base_index = mesh.positions.len() as u32;
+
+        for (k, u) in [(i, arc_length), (j, arc_length + edge_length)] {
+            for (point, length) in profile.iter().zip(&profile_lengths) {
+                let p = points[k] - miters[k] * point.inset;
+                mesh.positions.push(Vec3::new(p.x, point.y, p.y));
+                mesh.normals.push(Vec3::new(normal.x * point.normal.x, point.normal.y, normal.y * point.normal.x).normalize());
+                mesh.uvs.push(Vec2::new(u / perimeter, length / profile_length));
+            }
+        }
+
+        let rows = profile.len() as u32;
+        for r in 0..rows - 1 {
+            let indices = FlatTrapezeIndices {
+                lower_left: base_index + r,
+                upper_left: base_index + r + 1,
+                lower_right: base_index + rows + r,
+                upper_right: base_index + rows + r + 1,
+            };
+            indices.generate_triangles(&mut mesh.indices);
+        }
+
+        arc_length += edge_length;
+    }
+}
+
+impl TryFrom<ExtrudedPolygon> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(extrusion: ExtrudedPolygon) -> Result<Self, Self::Error> {
+
+        // Input parameter validation
+        check(extrusion.depth > 0.0, "depth", "must be positive")?;
+        check(extrusion.depth_segments >= 1, "depth_segments", "must be at least 1")?;
+        check(extrusion.bevel_width >= 0.0, "bevel_width", "must not be negative")?;
+        check(extrusion.bevel_width * 2.0 < extrusion.depth, "bevel_width", "must be less than half the depth")?;
+        check(extrusion.bevel_width == 0.0 || extrusion.bevel_segments >= 1, "bevel_segments", "must be at least 1 if there is a bevel")?;
+
+        // Triangulate before touching the points, so errors refer to the original indices
+        let mut triangles = triangulate_rings(&[&extrusion.polygon.points])?;
+
+        // Everything below expects the outline to be in counter-clockwise order
+        let mut points = extrusion.polygon.points.clone();
+        let signed_area: f32 = (0..points.len()).map(|i| points[i].perp_dot(points[(i + 1) % points.len()])).sum();
+        if signed_area < 0.0 {
+            points.reverse();
+            let last = points.len() as u32 - 1;
+            triangles.iter_mut().for_each(|i| *i = last - *i);
+        }
+
+        let miters = miters(&points);
+        check(miters.iter().all(|miter| miter.length() < MAX_MITER_LENGTH), "polygon", "must not have corners that fold back onto themselves")?;
+
+        // The shrunken caps need their own triangulation, shrinking can flip triangles of concave outlines
+        let cap_points: Vec<Vec2> = points.iter().zip(&miters).map(|(point, miter)| *point - *miter * extrusion.bevel_width).collect();
+        if extrusion.bevel_width > 0.0 {
+            triangles = triangulate_rings(&[&cap_points])
+                .map_err(|_| ShapeError::InvalidParameter { field: "bevel_width", constraint: "must be small enough for the caps not to intersect themselves" })?;
+        }
+
+        let profile = build_profile(&extrusion, extrusion.bevel_width);
+
+        let num_vertices = points.len() * 2 + points.len() * 2 * profile.len();
+        let num_indices = triangles.len() * 2 + points.len() * (profile.len() - 1) * 6;
+        let mut mesh = MeshData::new(num_vertices, num_indices);
+
+        add_caps(&mut mesh, &cap_points, &triangles, &extrusion);
+        add_walls(&mut mesh, &points, &miters, &profile);
+
+        let mut m = Mesh::new(PrimitiveTopology::TriangleList);
+        m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
+        m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
+        m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
+        m.set_indices(Some(Indices::U32(mesh.indices)));
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{Vec2, Vec3};
+    use bevy::prelude::Mesh;
+    use bevy::render::mesh::{Indices, VertexAttributeValues};
+    use crate::error::ShapeError;
+    use crate::polygon::Polygon;
+    use super::ExtrudedPolygon;
+
+    fn l_shape() -> Polygon {
+        Polygon {
+            points: vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 1.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(1.0, 2.0),
+                Vec2::new(0.0, 2.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn cap_triangles_face_the_cap_normal() {
+        for points in [l_shape().points, l_shape().points.into_iter().rev().collect()] {
+            let extrusion = ExtrudedPolygon {
+                bevel_width: 0.1,
+                bevel_segments: 2,
+                ..ExtrudedPolygon::new(Polygon { points }, 1.0)
+            };
+            let mesh = Mesh::try_from(extrusion).unwrap();
+
+            let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else { panic!() };
+            let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else { panic!() };
+            let Some(Indices::U32(indices)) = mesh.indices() else { panic!() };
+
+            let mut cap_triangles = 0;
+            for triangle in indices.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i] as usize]));
+                let normal = Vec3::from(normals[triangle[0] as usize]);
+                if normal.y.abs() == 1.0 && triangle.iter().all(|i| Vec3::from(normals[*i as usize]) == normal) {
+                    cap_triangles += 1;
+                    assert!((b - a).cross(c - a).dot(normal) > 0.0, "{:?} faces away from {:?}", [a, b, c], normal);
+                }
+            }
+            assert!(cap_triangles > 0);
+        }
+    }
+
+    #[test]
+    fn bevel_without_segments_is_rejected() {
+        let extrusion = ExtrudedPolygon {
+            bevel_width: 0.1,
+            ..ExtrudedPolygon::new(l_shape(), 1.0)
+        };
+        assert!(matches!(Mesh::try_from(extrusion), Err(ShapeError::InvalidParameter { field: "bevel_segments", .. })));
+    }
+
+    #[test]
+    fn spikes_are_rejected() {
+        let polygon = Polygon {
+            points: vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 0.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(0.5, 1.0),
+                Vec2::new(0.5, 1e6),
+                Vec2::new(0.5 - 1e-4, 1.0),
+                Vec2::new(0.0, 1.0),
+            ],
+        };
+        let extrusion = ExtrudedPolygon {
+            bevel_width: 0.1,
+            bevel_segments: 1,
+            ..ExtrudedPolygon::new(polygon, 1.0)
+        };
+        assert!(Mesh::try_from(extrusion).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::{App, Component, Mat3, Plugin, Quat, Query, Res, Time, Transform, Vec3};
+use crate::curves::ArcLengthCurve;
+use crate::error::ShapeError;
+use crate::ribbon::{self, ribbon_frames, Ribbon};
+use crate::tube::{self, calculate_frames, tube_frames, Curve, FrameAlgorithm, FrenetSerretFrame, Tube};
+
+/// Moves entities with a [`FollowCurve`] component along their curves.
+pub struct FollowCurvePlugin;
+
+impl Plugin for FollowCurvePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(follow_curves);
+    }
+}
+
+/// What happens when an entity reaches the end of its curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowMode {
+    /// Stop at the end.
+    Once,
+    /// Jump back to the start. Use this on closed curves to go around them endlessly.
+    Loop,
+    /// Turn around and go back to the start, then turn around again.
+    PingPong,
+}
+
+/// Moves an entity along a curve at a constant speed.
+/// The position is in the curve's coordinates, so make the entity a child of the tube it follows if the tube was moved.
+/// Tubes and ribbons fit their curve into the unit box by default, follow them with [`FollowCurve::along_tube`] or [`FollowCurve::along_ribbon`] to take that into account.
+#[derive(Component)]
+pub struct FollowCurve {
+    /// The distance travelled per second.
+    pub speed: f32,
+    pub mode: FollowMode,
+    /// Whether the entity is rotated to face the way it's moving, with its y axis along the binormal of the curve's frames.
+    pub align_to_tangent: bool,
+    /// The distance from the start of the curve. Set this to start somewhere else.
+    pub distance: f32,
+    // Whether the entity is currently moving from the end back to the start in ping-pong mode
+    returning: bool,
+    // Frames along the curve, with the distance of each one from the start
+    frames: Vec<FrenetSerretFrame>,
+    distances: Vec<f32>,
+}
+
+impl FollowCurve {
+    /// Follow the curve, oriented by parallel transport like a tube with default settings.
+    pub fn new(curve: impl Curve, speed: f32, mode: FollowMode) -> Self {
+        Self::with_frames(curve, speed, mode, FrameAlgorithm::ParallelTransport, None)
+    }
+
+    /// Follow the curve with the given frame algorithm and initial normal.
+    /// The frames are sampled at evenly spaced distances, independently of any mesh. Use [`FollowCurve::along_tube`] or [`FollowCurve::along_ribbon`] to follow one exactly.
+    pub fn with_frames(curve: impl Curve, speed: f32, mode: FollowMode, frame_algorithm: FrameAlgorithm, initial_normal: Option<Vec3>) -> Self {
+        const SAMPLES: u32 = 1024;
+
+        let curve = ArcLengthCurve::new(curve);
+        Self::on_frames(calculate_frames(&curve, SAMPLES + 1, frame_algorithm, initial_normal), speed, mode)
+    }
+
+    /// Follow the center line of the tube, on the same frames as its rings, so the entity stays aligned with the mesh.
+    /// This includes the tube's segmentation, twist and normalization. Fails for the same settings the tube's mesh would fail for.
+    pub fn along_tube(tube: &Tube, speed: f32, mode: FollowMode) -> Result<Self, ShapeError> {
+        tube::validate(tube)?;
+        let (_, frames) = tube_frames(tube);
+        Ok(Self::on_frames(frames, speed, mode))
+    }
+
+    /// Follow the center line of the ribbon, on the same frames as its cross-sections, so the entity stays aligned with the mesh.
+    /// Fails for the same settings the ribbon's mesh would fail for.
+    pub fn along_ribbon(ribbon: &Ribbon, speed: f32, mode: FollowMode) -> Result<Self, ShapeError> {
+        ribbon::validate(ribbon)?;
+        Ok(Self::on_frames(ribbon_frames(ribbon), speed, mode))
+    }
+
+    // Moves in straight lines between the frames, like the edges of the mesh built on them
+    fn on_frames(frames: Vec<FrenetSerretFrame>, speed: f32, mode: FollowMode) -> Self {
+        let mut distances = Vec::with_capacity(frames.len());
+        let mut distance = 0.0;
+        for (i, frame) in frames.iter().enumerate() {
+            if i > 0 {
+                distance += frame.origin.distance(frames[i - 1].origin);
+            }
+            distances.push(distance);
+        }
+        Self {
+            speed,
+            mode,
+            align_to_tangent: true,
+            distance: 0.0,
+            returning: false,
+            frames,
+            distances,
+        }
+    }
+
+    /// The length of the curve.
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Move along the curve by the distance travelled in the given time.
+    pub fn advance(&mut self, seconds: f32) {
+        let length = self.length();
+        if length <= 0.0 {
+            return;
+        }
+        let step = self.speed * seconds;
+        match self.mode {
+            FollowMode::Once => {
+                self.distance = (self.distance + step).clamp(0.0, length);
+            }
+            FollowMode::Loop => {
+                self.distance = (self.distance + step).rem_euclid(length);
+            }
+            FollowMode::PingPong => {
+                // Unfold the back and forth into a loop of twice the length
+                let unfolded = if self.returning { 2.0 * length - self.distance } else { self.distance };
+                let unfolded = (unfolded + step).rem_euclid(2.0 * length);
+                self.returning = unfolded > length;
+                self.distance = if self.returning { 2.0 * length - unfolded } else { unfolded };
+            }
+        }
+    }
+
+    /// The transform at the current distance along the curve.
+    pub fn transform(&self) -> Transform {
+        self.transform_at(self.distance)
+    }
+
+    /// The transform at some distance along the curve.
+    /// Facing the way the entity moves means looking along -z, Bevy's forward direction.
+    pub fn transform_at(&self, distance: f32) -> Transform {
+        // The segment the distance falls into
+        let last = self.frames.len() - 1;
+        let j = self.distances.partition_point(|&d| d < distance).clamp(last.min(1), last);
+        let i = j.saturating_sub(1);
+        let span = self.distances[j] - self.distances[i];
+        let s = if span > 0.0 { ((distance - self.distances[i]) / span).clamp(0.0, 1.0) } else { 0.0 };
+        let (a, b) = (&self.frames[i], &self.frames[j]);
+
+        let origin = a.origin.lerp(b.origin, s);
+        if !self.align_to_tangent {
+            return Transform::from_translation(origin);
+        }
+
+        let backwards = self.returning != (self.speed < 0.0);
+        let direction = if backwards { -1.0 } else { 1.0 };
+        let forward = direction * a.tangent.lerp(b.tangent, s).normalize();
+        let up = a.binormal.lerp(b.binormal, s).reject_from_normalized(forward).normalize();
+        let rotation = Quat::from_mat3(&Mat3::from_cols(up.cross(-forward), up, -forward));
+        Transform::from_translation(origin).with_rotation(rotation)
+    }
+}
+
+/// Moves every entity with a [`FollowCurve`] along its curve. Added by the [`FollowCurvePlugin`].
+pub fn follow_curves(time: Res<Time>, mut query: Query<(&mut FollowCurve, &mut Transform)>) {
+    for (mut follow, mut transform) in query.iter_mut() {
+        follow.advance(time.delta_seconds());
+        let target = follow.transform();
+        transform.translation = target.translation;
+        if follow.align_to_tangent {
+            transform.rotation = target.rotation;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec3;
+    use crate::curves::CatmullRom;
+    use crate::tube::{tube_frames, AdaptiveSegmentation, Tube};
+    use super::{FollowCurve, FollowMode};
+
+    #[test]
+    fn passes_through_the_rings_of_the_tube() {
+        let tube = || Tube {
+            curve: Box::new(CatmullRom {
+                points: vec![Vec3::ZERO, Vec3::new(1.0, 0.5, 0.0), Vec3::new(2.0, 0.0, 1.0), Vec3::new(3.0, 1.0, 0.0)],
+                closed: false,
+            }),
+            adaptive_segmentation: Some(AdaptiveSegmentation::default()),
+            ..Default::default()
+        };
+        let follow = FollowCurve::along_tube(&tube(), 1.0, FollowMode::Once).unwrap();
+        let (_, frames) = tube_frames(&tube());
+
+        let mut distance = 0.0;
+        for (i, frame) in frames.iter().enumerate() {
+            if i > 0 {
+                distance += frame.origin.distance(frames[i - 1].origin);
+            }
+            let transform = follow.transform_at(distance);
+            assert!(transform.translation.distance(frame.origin) < 1e-4, "ring {i} is off");
+            assert!(transform.up().distance(frame.binormal) < 1e-3, "ring {i} is rotated");
+        }
+        assert!((follow.length() - distance).abs() < 1e-4);
+    }
+
+    #[test]
+    fn invalid_tubes_are_rejected() {
+        let tube = Tube {
+            adaptive_segmentation: Some(AdaptiveSegmentation { min_segments: 10, max_segments: 5, ..Default::default() }),
+            ..Default::default()
+        };
+        assert!(FollowCurve::along_tube(&tube, 1.0, FollowMode::Once).is_err());
+    }
+}
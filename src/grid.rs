@@ -1,5 +1,6 @@
 use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_resource::PrimitiveTopology;
+use crate::error::{check, ShapeError};
 use crate::util::FlatTrapezeIndices;
 
 pub struct Grid {
@@ -35,14 +36,17 @@ impl Grid {
     }
 }
 
-impl From<Grid> for Mesh {
-    fn from(grid: Grid) -> Self {
+impl TryFrom<Grid> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(grid: Grid) -> Result<Self, Self::Error> {
 
         // Validate input parameters
-        assert!(grid.width_segments > 0, "A grid must have segments");
-        assert!(grid.height_segments > 0, "A grid must have segments");
-        assert!(grid.width > 0.0, "A grid must have positive width");
-        assert!(grid.height > 0.0, "A grid must have positive height");
+        check(grid.width_segments > 0, "width_segments", "must be at least 1")?;
+        check(grid.height_segments > 0, "height_segments", "must be at least 1")?;
+        check(grid.width > 0.0, "width", "must be positive")?;
+        check(grid.height > 0.0, "height", "must be positive")?;
 
         let num_points = (grid.height_segments + 1) * (grid.width_segments + 1);
         let num_faces = grid.height_segments * grid.width_segments;
@@ -94,6 +98,6 @@ impl From<Grid> for Mesh {
         mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
         mesh.set_indices(Some(Indices::U32(indices)));
-        mesh
+        Ok(mesh)
     }
 }
\ No newline at end of file
@@ -1,7 +1,14 @@
+pub mod cable;
 pub mod cone;
+pub mod curves;
 pub mod cylinder;
+pub mod error;
+pub mod extruded_polygon;
+pub mod follow;
 pub mod grid;
 pub mod polygon;
+pub mod ribbon;
+pub mod sweep;
 pub mod torus;
 pub mod tube;
 pub(crate) mod util;
@@ -16,17 +23,23 @@ struct MeshData {
 impl MeshData {
     fn new(num_vertices: usize, num_indices: usize) -> Self {
         Self {
-            positions: Vec::with_capacity(num_vertices as usize),
-            normals: Vec::with_capacity(num_vertices as usize),
-            uvs: Vec::with_capacity(num_vertices as usize),
-            indices: Vec::with_capacity(num_indices as usize),
+            positions: Vec::with_capacity(num_vertices),
+            normals: Vec::with_capacity(num_vertices),
+            uvs: Vec::with_capacity(num_vertices),
+            indices: Vec::with_capacity(num_indices),
         }
     }
 }
 
 use bevy::prelude::{Vec2, Vec3};
+pub use crate::cable::Cable;
 pub use crate::cone::Cone;
 pub use crate::cylinder::Cylinder;
+pub use crate::error::ShapeError;
+pub use crate::extruded_polygon::ExtrudedPolygon;
+pub use crate::follow::{FollowCurve, FollowCurvePlugin};
 pub use crate::grid::Grid;
-pub use crate::polygon::Polygon;
+pub use crate::polygon::{Polygon, PolygonWithHoles};
+pub use crate::ribbon::Ribbon;
+pub use crate::sweep::Sweep;
 pub use crate::torus::Torus;
\ No newline at end of file
@@ -1,10 +1,10 @@
 use std::error::Error;
-use std::fmt::{Display, Formatter};
 use bevy::math::{Rect, Vec2, Vec3};
 use bevy::prelude::Mesh;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
-use triangulate::{ListFormat, TriangulationError, Vertex};
+use triangulate::{ListFormat, TrapezoidationError, TriangulationError, Vertex};
 use triangulate::formats::IndexedListFormat;
+use crate::error::{PolygonError, ShapeError};
 
 pub struct Polygon {
     /// Points on a path where the last and first point are connected to form a closed circle.
@@ -49,11 +49,39 @@ impl Polygon {
     }
 }
 
-fn bounding_rect_for_points<'a>(points: impl Iterator<Item = &'a Vec2>) -> Rect {
-    let mut x_min = 0.0f32;
-    let mut x_max = 0.0f32;
-    let mut y_min = 0.0f32;
-    let mut y_max = 0.0f32;
+/// A polygon with holes cut into it.
+pub struct PolygonWithHoles {
+    /// The outer boundary. The same rules as for [`Polygon::points`] apply.
+    pub outline: Vec<Vec2>,
+    /// Closed paths inside of the outline that are cut out of the polygon.
+    /// Holes must not intersect the outline or each other and must not be nested inside of other holes.
+    pub holes: Vec<Vec<Vec2>>,
+}
+
+impl PolygonWithHoles {
+    /// Creates a regular n-gon with a smaller, aligned n-gon cut out of its center.
+    pub fn new_regular_ngon_ring(outer_radius: f32, inner_radius: f32, n: usize) -> PolygonWithHoles {
+        PolygonWithHoles {
+            outline: Polygon::new_regular_ngon(outer_radius, n).points,
+            holes: vec![Polygon::new_regular_ngon(inner_radius, n).points],
+        }
+    }
+}
+
+impl From<Polygon> for PolygonWithHoles {
+    fn from(polygon: Polygon) -> Self {
+        PolygonWithHoles {
+            outline: polygon.points,
+            holes: Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn bounding_rect_for_points<'a>(points: impl Iterator<Item = &'a Vec2>) -> Rect {
+    let mut x_min = f32::MAX;
+    let mut x_max = f32::MIN;
+    let mut y_min = f32::MAX;
+    let mut y_max = f32::MIN;
 
     for point in points {
         x_min = x_min.min(point.x);
@@ -85,83 +113,218 @@ impl Vertex for Vec2f {
     }
 }
 
-/// The input must not be empty.
-/// No edge can cross any other edge, whether it is on the same polygon or not.
-/// Each vertex must be part of exactly two edges. Polygons cannot 'share' vertices with each other.
-/// Each vertex must be distinct - no vertex can have x and y coordinates that both compare equal to another vertex's.
-#[derive(Debug)]
-pub struct InvalidInput;
-
-impl Display for InvalidInput {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Invalid polygon input")
+impl<T: Error> From<TriangulationError<T>> for ShapeError {
+    fn from(value: TriangulationError<T>) -> Self {
+        let error = match value {
+            TriangulationError::TrapezoidationError(TrapezoidationError::NotEnoughVertices(n)) => PolygonError::NotEnoughPoints(n),
+            TriangulationError::NoVertices => PolygonError::NotEnoughPoints(0),
+            _ => PolygonError::Triangulation(value.to_string()),
+        };
+        Self::InvalidPolygon(error)
     }
 }
 
-impl Error for InvalidInput { }
+// The sign tells on which side of the line through a and b the point c lies. 0 if it's on the line.
+fn orientation(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).perp_dot(c - a)
+}
 
-impl<T: Error> From<TriangulationError<T>> for InvalidInput {
-    fn from(value: TriangulationError<T>) -> Self {
-        match value {
-            TriangulationError::TrapezoidationError(_) => panic!("Failed to triangulate: {}", value),
-            TriangulationError::NoVertices => Self,
-            TriangulationError::InternalError(_) => Self,
-            TriangulationError::FanBuilder(_) => panic!("Failed to triangulate: {}", value),
-            _ => panic!("Failed to triangulate: {}", value),
+// Checks whether p lies within the bounding box of the segment a-b. Only meaningful if p is on the line through a and b.
+fn within_segment(a: Vec2, b: Vec2, p: Vec2) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+// Checks whether two segments have at least one point in common.
+fn segments_intersect(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> bool {
+    let d0 = orientation(b0, b1, a0);
+    let d1 = orientation(b0, b1, a1);
+    let d2 = orientation(a0, a1, b0);
+    let d3 = orientation(a0, a1, b1);
+
+    // Proper crossing
+    if d0 * d1 < 0.0 && d2 * d3 < 0.0 {
+        return true;
+    }
+
+    // One end point touches the other segment
+    (d0 == 0.0 && within_segment(b0, b1, a0))
+        || (d1 == 0.0 && within_segment(b0, b1, a1))
+        || (d2 == 0.0 && within_segment(a0, a1, b0))
+        || (d3 == 0.0 && within_segment(a0, a1, b1))
+}
+
+// Point-in-polygon test using the even-odd rule. Points on the boundary may go either way.
+fn contains_point(ring: &[Vec2], p: Vec2) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let a = ring[i];
+        let b = ring[(i + 1) % ring.len()];
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
         }
     }
+    inside
 }
 
-impl TryFrom<Polygon> for Mesh {
+// The triangulation library doesn't tell us where the input is broken, and it might not detect it at all.
+// So we check the preconditions ourselves beforehand. This is quadratic in the number of points.
+// The first ring is the outline, all others are holes. Indices in errors refer to the rings' points in order.
+fn validate_rings(rings: &[&[Vec2]]) -> Result<(), PolygonError> {
+
+    for ring in rings {
+        if ring.len() < 3 {
+            return Err(PolygonError::NotEnoughPoints(ring.len()));
+        }
+    }
 
-    type Error = InvalidInput;
+    // Flatten the rings, so edge i goes from points[i] to points[next[i]]
+    let points: Vec<Vec2> = rings.iter().flat_map(|ring| ring.iter().copied()).collect();
+    let mut next = Vec::with_capacity(points.len());
+    for ring in rings {
+        let base = next.len();
+        next.extend((1..ring.len()).map(|i| base + i));
+        next.push(base);
+    }
+    let n = points.len();
 
-    fn try_from(polygon: Polygon) -> Result<Self, Self::Error> {
+    if let Some(i) = points.iter().position(|p| !p.is_finite()) {
+        return Err(PolygonError::NonFinitePoint(i));
+    }
 
-        if polygon.points.len() < 3 {
-            return Err(InvalidInput);
+    for i in 0..n {
+        if points[i] == points[next[i]] {
+            return Err(PolygonError::DegenerateEdge(i));
         }
+    }
+
+    for i in 0..n {
+        for j in i + 1..n {
+            if points[i] == points[j] {
+                return Err(PolygonError::DuplicatePoint { first: i, second: j });
+            }
+        }
+    }
 
-        let mut positions: Vec<[f32; 3]> = Vec::with_capacity(polygon.points.len());
-        let mut normals: Vec<[f32; 3]> = Vec::with_capacity(polygon.points.len());
-        let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(polygon.points.len());
+    for i in 0..n {
+        let a0 = points[i];
+        let a1 = points[next[i]];
 
-        // The domain is needed for UV mapping. The domain tells us how to transform all points to optimally fit the 0-1 range.
-        let domain = bounding_rect_for_points(polygon.points.iter());
+        for j in i + 1..n {
+            let b0 = points[j];
+            let b1 = points[next[j]];
 
-        // Add the vertices
-        for v in &polygon.points {
-            positions.push([v.x, 0.0, v.y]);
-            normals.push(Vec3::Y.to_array());
+            let intersects = if next[i] == j {
+                // Adjacent edges share a point. They only intersect if the second one folds back onto the first.
+                orientation(a0, a1, b1) == 0.0 && (a0 - a1).dot(b1 - a1) > 0.0
+            }
+            else if next[j] == i {
+                // The last edge of a ring is adjacent to the first one
+                orientation(b0, a0, a1) == 0.0 && (b0 - a0).dot(a1 - a0) > 0.0
+            }
+            else {
+                segments_intersect(a0, a1, b0, b1)
+            };
 
-            // Transform the polygon domain to the 0-1 UV domain.
-            let u = (v.x - domain.min.x) / (domain.max.x - domain.min.x);
-            let v = (v.y - domain.min.y) / (domain.max.y - domain.min.y);
-            uvs.push([u, v]);
+            if intersects {
+                return Err(PolygonError::SelfIntersection { first_edge: i, second_edge: j });
+            }
         }
+    }
+
+    // Since no edges intersect, a single point tells us whether a whole hole is in the right place.
+    for (hole_idx, hole) in rings.iter().enumerate().skip(1) {
+        let misplaced = !contains_point(rings[0], hole[0]) || rings.iter()
+            .enumerate()
+            .skip(1)
+            .any(|(other_idx, other)| other_idx != hole_idx && contains_point(other, hole[0]));
+        if misplaced {
+            return Err(PolygonError::MisplacedHole(hole_idx - 1));
+        }
+    }
+
+    Ok(())
+}
+
+// Triangulates the area enclosed by the rings. The first ring is the outline, all others are holes.
+// Returns indices into the rings' points in order, wound so the triangles face y+ once placed in the xz plane.
+pub(crate) fn triangulate_rings(rings: &[&[Vec2]]) -> Result<Vec<u32>, ShapeError> {
+
+    validate_rings(rings)?;
+
+    // Each triangle vertex is identified by its ring and its index within that ring
+    let mut ring_offsets = Vec::with_capacity(rings.len());
+    let mut offset = 0;
+    for ring in rings {
+        ring_offsets.push(offset);
+        offset += ring.len();
+    }
+
+    // This library is terrible to use. The heck is that initializer object. And this trait madness.
+    let polygons = rings.iter()
+        .map(|ring| ring.iter().copied().map(Vec2f).collect::<Vec<Vec2f>>())
+        .collect::<Vec<Vec<Vec2f>>>();
+    let mut output = Vec::<[[usize; 2]; 3]>::new();
+    let format = IndexedListFormat::new(&mut output).into_fan_format();
+    triangulate::PolygonList::triangulate(&polygons, format)?;
+    let indices = output.into_iter()
+        .flat_map(|[a, b, c]| [c, b, a])
+        .map(|[ring, idx]| (ring_offsets[ring] + idx) as u32)
+        .collect();
+
+    Ok(indices)
+}
+
+// Builds a flat mesh in the xz plane facing y+. The first ring is the outline, all others are holes.
+fn flat_mesh(rings: &[&[Vec2]]) -> Result<Mesh, ShapeError> {
+
+    let indices = triangulate_rings(rings)?;
+
+    let num_vertices = rings.iter().map(|ring| ring.len()).sum();
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(num_vertices);
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(num_vertices);
+    let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(num_vertices);
+
+    // The domain is needed for UV mapping. The domain tells us how to transform all points to optimally fit the 0-1 range.
+    // Holes are inside the outline, so the outline alone determines it.
+    let domain = bounding_rect_for_points(rings[0].iter());
+
+    // Add the vertices
+    for v in rings.iter().flat_map(|ring| ring.iter()) {
+        positions.push([v.x, 0.0, v.y]);
+        normals.push(Vec3::Y.to_array());
+
+        // Transform the polygon domain to the 0-1 UV domain.
+        let u = (v.x - domain.min.x) / (domain.max.x - domain.min.x);
+        let v = (v.y - domain.min.y) / (domain.max.y - domain.min.y);
+        uvs.push([u, v]);
+    }
+
+    // Put the mesh together
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    Ok(mesh)
+}
+
+impl TryFrom<Polygon> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(polygon: Polygon) -> Result<Self, Self::Error> {
+        flat_mesh(&[&polygon.points])
+    }
+}
+
+impl TryFrom<PolygonWithHoles> for Mesh {
+
+    type Error = ShapeError;
 
-        // Triangulate to obtain the indices
-        // This library is terrible to use. The heck is that initializer object. And this trait madness.
-        let polygons = polygon
-            .points
-            .into_iter()
-            .map(|v| Vec2f(v))
-            .collect::<Vec<Vec2f>>();
-        let mut output = Vec::<[usize; 3]>::new();
-        let format = IndexedListFormat::new(&mut output).into_fan_format();
-        triangulate::Polygon::triangulate(&polygons, format)?;
-        let indices = output.into_iter()
-            .map(|[a, b, c]| [c, b, a])
-            .flatten()
-            .map(|v| v as u32)
-            .collect();
-
-        // Put the mesh together
-        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-        mesh.set_indices(Some(Indices::U32(indices)));
-        Ok(mesh)
+    fn try_from(polygon: PolygonWithHoles) -> Result<Self, Self::Error> {
+        let mut rings: Vec<&[Vec2]> = Vec::with_capacity(polygon.holes.len() + 1);
+        rings.push(&polygon.outline);
+        rings.extend(polygon.holes.iter().map(|hole| hole.as_slice()));
+        flat_mesh(&rings)
     }
 }
@@ -0,0 +1,196 @@
+use std::ops::Deref;
+use bevy::math::{Vec2, Vec3};
+use bevy::prelude::Mesh;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use crate::MeshData;
+use crate::error::{check, ShapeError};
+use crate::tube::{calculate_frames, normalize_frames, twist_frames, Curve, DefaultCurve, FrameAlgorithm, FrenetSerretFrame, TwistProfile};
+use crate::util::FlatTrapezeIndices;
+
+/// A flat strap that follows a curve, like a road, a belt or a banner.
+/// Its width spans along the normal of the curve's frames and its thickness along their binormal.
+pub struct Ribbon {
+    pub curve: Box<dyn Curve>,
+    pub width: f32,
+    /// 0 for a strip without side walls that is visible from both sides.
+    /// Thick ribbons are closed off at both ends, unless the curve is closed.
+    pub thickness: f32,
+    pub length_segments: u32,
+    /// Whether the curve is recentered and scaled to fit into the unit box.
+    /// Disable this to keep the curve's coordinates, the width and thickness are then measured in the curve's units as well.
+    pub normalize: bool,
+    /// How the ribbon is oriented along the curve. Use a fixed up vector for roads that never bank.
+    pub frame_algorithm: FrameAlgorithm,
+    /// The normal at the start of the curve, for frame algorithms that don't determine it themselves.
+    /// If this is None, or parallel to the tangent, a direction perpendicular to the tangent is picked.
+    pub initial_normal: Option<Vec3>,
+    /// Rotates the ribbon around its curve on top of the frame algorithm. Positive angles follow the right-hand rule around the tangent.
+    pub twist: Option<Box<dyn TwistProfile>>,
+}
+
+impl Default for Ribbon {
+    fn default() -> Self {
+        Ribbon {
+            curve: Box::new(DefaultCurve), // straight line
+            width: 0.1,
+            thickness: 0.01,
+            length_segments: 64,
+            normalize: true,
+            frame_algorithm: FrameAlgorithm::ParallelTransport,
+            initial_normal: None,
+            twist: None,
+        }
+    }
+}
+
+// One of the long faces of the ribbon. Corners are given as (normal, binormal) coordinates.
+// The texture's v axis goes from the first corner to the second one.
+struct Face {
+    from: Vec2,
+    to: Vec2,
+    normal: Vec2,
+}
+
+// The faces going around the cross-section, so the texture on each of them reads the right way around
+fn faces(ribbon: &Ribbon) -> Vec<Face> {
+    let w = ribbon.width / 2.0;
+    let h = ribbon.thickness / 2.0;
+    let top = Face { from: Vec2::new(-w, h), to: Vec2::new(w, h), normal: Vec2::Y };
+    let bottom = Face { from: Vec2::new(w, -h), to: Vec2::new(-w, -h), normal: -Vec2::Y };
+
+    if ribbon.thickness > 0.0 {
+        vec![
+            top,
+            Face { from: Vec2::new(w, h), to: Vec2::new(w, -h), normal: Vec2::X },
+            bottom,
+            Face { from: Vec2::new(-w, -h), to: Vec2::new(-w, h), normal: -Vec2::X },
+        ]
+    }
+    else {
+        vec![top, bottom]
+    }
+}
+
+fn add_ribbon_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, faces: &[Face], u: f32) {
+    for face in faces {
+        let normal = face.normal.x * frame.normal + face.normal.y * frame.binormal;
+        for (corner, v) in [(face.from, 0.0), (face.to, 1.0)] {
+            mesh.positions.push(frame.origin + corner.x * frame.normal + corner.y * frame.binormal);
+            mesh.normals.push(normal);
+            mesh.uvs.push(Vec2::new(u, v));
+        }
+    }
+}
+
+fn index_ribbon(mesh: &mut MeshData, ribbon: &Ribbon, num_faces: u32) {
+    let stride = num_faces * 2;
+    for ls in 0..ribbon.length_segments {
+        for face in 0..num_faces {
+            let lower = ls * stride + face * 2;
+            let upper = lower + stride;
+            let indices = FlatTrapezeIndices {
+                lower_left: lower,
+                upper_left: upper,
+                lower_right: lower + 1,
+                upper_right: upper + 1,
+            };
+            indices.generate_triangles(&mut mesh.indices);
+        }
+    }
+}
+
+// The direction is -1 for the start of the ribbon and 1 for its end
+fn add_end(mesh: &mut MeshData, frame: &FrenetSerretFrame, ribbon: &Ribbon, direction: f32) {
+
+    let w = ribbon.width / 2.0;
+    let h = ribbon.thickness / 2.0;
+    let base_index = mesh.positions.len() as u32;
+
+    let corners = [
+        (Vec2::new(-w, h), Vec2::new(0.0, 0.0)),
+        (Vec2::new(w, h), Vec2::new(1.0, 0.0)),
+        (Vec2::new(w, -h), Vec2::new(1.0, 1.0)),
+        (Vec2::new(-w, -h), Vec2::new(0.0, 1.0)),
+    ];
+    for (corner, uv) in corners {
+        mesh.positions.push(frame.origin + corner.x * frame.normal + corner.y * frame.binormal);
+        mesh.normals.push(direction * frame.tangent);
+        mesh.uvs.push(uv);
+    }
+
+    // Mirror the quad at the end, so both ends face outwards
+    let indices = if direction < 0.0 {
+        FlatTrapezeIndices {
+            lower_left: base_index + 3,
+            upper_left: base_index,
+            lower_right: base_index + 2,
+            upper_right: base_index + 1,
+        }
+    }
+    else {
+        FlatTrapezeIndices {
+            lower_left: base_index + 2,
+            upper_left: base_index + 1,
+            lower_right: base_index + 3,
+            upper_right: base_index,
+        }
+    };
+    indices.generate_triangles(&mut mesh.indices);
+}
+
+// Input parameter validation, shared with the curve follower
+pub(crate) fn validate(ribbon: &Ribbon) -> Result<(), ShapeError> {
+    check(ribbon.width > 0.0, "width", "must be positive")?;
+    check(ribbon.thickness >= 0.0, "thickness", "must not be negative")?;
+    check(ribbon.length_segments > 0, "length_segments", "must be at least 1")?;
+    Ok(())
+}
+
+// The frames the cross-sections of the ribbon are placed on. Shared with the curve follower.
+pub(crate) fn ribbon_frames(ribbon: &Ribbon) -> Vec<FrenetSerretFrame> {
+    let mut frames = calculate_frames(ribbon.curve.deref(), ribbon.length_segments + 1, ribbon.frame_algorithm, ribbon.initial_normal);
+    if let Some(twist) = &ribbon.twist {
+        let step = 1.0 / ribbon.length_segments as f32;
+        let ts: Vec<f32> = (0..frames.len()).map(|idx| step * idx as f32).collect();
+        twist_frames(frames.as_mut_slice(), &ts, twist.deref());
+    }
+    if ribbon.normalize {
+        normalize_frames(frames.as_mut_slice());
+    }
+    frames
+}
+
+impl TryFrom<Ribbon> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(ribbon: Ribbon) -> Result<Self, Self::Error> {
+
+        validate(&ribbon)?;
+
+        let faces = faces(&ribbon);
+        let has_ends = ribbon.thickness > 0.0 && !ribbon.curve.is_closed();
+
+        let num_vertices = (ribbon.length_segments + 1) as usize * faces.len() * 2 + if has_ends { 8 } else { 0 };
+        let num_indices = ribbon.length_segments as usize * faces.len() * 6 + if has_ends { 12 } else { 0 };
+        let mut mesh = MeshData::new(num_vertices, num_indices);
+
+        let frames = ribbon_frames(&ribbon);
+        let step = 1.0 / ribbon.length_segments as f32;
+        for (idx, frame) in frames.iter().enumerate() {
+            add_ribbon_segment(&mut mesh, frame, &faces, step * idx as f32);
+        }
+        index_ribbon(&mut mesh, &ribbon, faces.len() as u32);
+        if has_ends {
+            add_end(&mut mesh, &frames[0], &ribbon, -1.0);
+            add_end(&mut mesh, &frames[frames.len() - 1], &ribbon, 1.0);
+        }
+
+        let mut m = Mesh::new(PrimitiveTopology::TriangleList);
+        m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
+        m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
+        m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
+        m.set_indices(Some(Indices::U32(mesh.indices)));
+        Ok(m)
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::Deref;
+use bevy::math::{Vec2, Vec3};
+use bevy::prelude::Mesh;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use crate::MeshData;
+use crate::error::{check, PolygonError, ShapeError};
+use crate::polygon::Polygon;
+use crate::tube::{calculate_frames, normalize_frames, twist_frames, Curve, DefaultCurve, FrameAlgorithm, FrenetSerretFrame, TwistProfile};
+use crate::util::FlatTrapezeIndices;
+
+/// How the surface is shaded along the edge a profile point traces out when it is swept along the curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeShading {
+    /// The normals of both adjacent faces are averaged, so the edge appears round.
+    Smooth,
+    /// Both adjacent faces keep their own normals, so the edge appears sharp.
+    Hard,
+}
+
+/// A 2d cross-section that is swept along a curve.
+pub struct Profile {
+    /// The points of the cross-section. x is mapped onto the normal of the curve, y onto its binormal.
+    /// The surface faces to the right of the path from one point to the next,
+    /// which is the outside of a counter-clockwise closed profile. Closed profiles are reordered to be counter-clockwise.
+    /// Every point must be distinct, a closed profile must not repeat its first point at the end.
+    pub points: Vec<Vec2>,
+    /// Whether the last point is connected back to the first one.
+    pub closed: bool,
+    /// The shading for each point. Must either be empty to shade every point smoothly or contain one entry per point.
+    pub shading: Vec<EdgeShading>,
+}
+
+impl Profile {
+    /// Create a closed profile where every point is shaded the same way.
+    pub fn new_closed(points: Vec<Vec2>, shading: EdgeShading) -> Self {
+        let shading = vec![shading; points.len()];
+        Self { points, closed: true, shading }
+    }
+
+    /// Create an open profile where every point is shaded the same way.
+    pub fn new_open(points: Vec<Vec2>, shading: EdgeShading) -> Self {
+        let shading = vec![shading; points.len()];
+        Self { points, closed: false, shading }
+    }
+
+    /// Create a rectangle with hard edges centered on the curve.
+    pub fn new_rectangle(width: f32, height: f32) -> Self {
+        let x = width / 2.0;
+        let y = height / 2.0;
+        Self::new_closed(vec![
+            Vec2::new(-x, -y),
+            Vec2::new(x, -y),
+            Vec2::new(x, y),
+            Vec2::new(-x, y),
+        ], EdgeShading::Hard)
+    }
+
+    fn shading_at(&self, i: usize) -> EdgeShading {
+        self.shading.get(i).copied().unwrap_or(EdgeShading::Smooth)
+    }
+}
+
+// Identical points would leave edges without a direction, and so without a normal.
+// Unlike polygons, profiles may cross themselves, so this is all that is checked.
+fn validate_points(profile: &Profile) -> Result<(), PolygonError> {
+    let points = &profile.points;
+    let n = points.len();
+    let num_edges = if profile.closed { n } else { n - 1 };
+
+    for i in 0..num_edges {
+        if points[i] == points[(i + 1) % n] {
+            return Err(PolygonError::DegenerateEdge(i));
+        }
+    }
+
+    for i in 0..n {
+        for j in i + 1..n {
+            if points[i] == points[j] {
+                return Err(PolygonError::DuplicatePoint { first: i, second: j });
+            }
+        }
+    }
+    Ok(())
+}
+
+impl From<Polygon> for Profile {
+    fn from(polygon: Polygon) -> Self {
+        Self::new_closed(polygon.points, EdgeShading::Hard)
+    }
+}
+
+/// Sweeps a 2d profile along a curve. This is a generalization of the tube, which always sweeps a circle.
+pub struct Sweep {
+    pub curve: Box<dyn Curve>,
+    pub profile: Profile,
+    pub length_segments: u32,
+    /// Whether the curve is recentered and scaled to fit into the unit box.
+    /// Disable this to keep the curve's coordinates, the profile is then measured in the curve's units as well.
+    pub normalize: bool,
+    /// How the profile is oriented along the curve.
+    pub frame_algorithm: FrameAlgorithm,
+    /// The normal at the start of the curve, for frame algorithms that don't determine it themselves.
+    /// If this is None, or parallel to the tangent, a direction perpendicular to the tangent is picked.
+    pub initial_normal: Option<Vec3>,
+    /// Rotates the profile around the curve on top of the frame algorithm. Positive angles follow the right-hand rule around the tangent.
+    pub twist: Option<Box<dyn TwistProfile>>,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep {
+            curve: Box::new(DefaultCurve), // straight line
+            profile: Profile::new_rectangle(0.1, 0.1),
+            length_segments: 64,
+            normalize: true,
+            frame_algorithm: FrameAlgorithm::ParallelTransport,
+            initial_normal: None,
+            twist: None,
+        }
+    }
+}
+
+// A point of the profile as it ends up in the mesh. Points with hard edges are split into two of these.
+struct ProfileVertex {
+    position: Vec2,
+    normal: Vec2,
+    v: f32,
+}
+
+// Splits the profile into segments with a start and an end vertex each
+fn profile_vertices(profile: &Profile, points: &[Vec2], shading: &[EdgeShading]) -> Vec<ProfileVertex> {
+
+    let n = points.len();
+    let num_segments = if profile.closed { n } else { n - 1 };
+
+    // The surface faces to the right of each segment
+    let segment_normals: Vec<Vec2> = (0..num_segments)
+        .map(|i| {
+            let d = points[(i + 1) % n] - points[i];
+            Vec2::new(d.y, -d.x).normalize_or_zero()
+        })
+        .collect();
+    let total_length: f32 = (0..num_segments).map(|i| points[i].distance(points[(i + 1) % n])).sum();
+
+    // The normal of a point where it meets the given segment
+    let normal_at = |point: usize, segment: usize| -> Vec2 {
+        let neighbour = if point == segment {
+            // Start of the segment, the neighbour is the segment before
+            if point > 0 { Some(point - 1) } else if profile.closed { Some(num_segments - 1) } else { None }
+        }
+        else {
+            // End of the segment, the neighbour is the segment after
+            if segment + 1 < num_segments { Some(segment + 1) } else if profile.closed { Some(0) } else { None }
+        };
+        match (shading[point], neighbour) {
+            (EdgeShading::Smooth, Some(neighbour)) => (segment_normals[segment] + segment_normals[neighbour]).normalize_or_zero(),
+            _ => segment_normals[segment],
+        }
+    };
+
+    let mut out = Vec::with_capacity(num_segments * 2);
+    let mut length = 0.0;
+    for i in 0..num_segments {
+        let j = (i + 1) % n;
+        let segment_length = points[i].distance(points[j]);
+        out.push(ProfileVertex {
+            position: points[i],
+            normal: normal_at(i, i),
+            v: length / total_length,
+        });
+        length += segment_length;
+        out.push(ProfileVertex {
+            position: points[j],
+            normal: normal_at(j, i),
+            v: length / total_length,
+        });
+    }
+
+    out
+}
+
+fn add_sweep_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, profile: &[ProfileVertex], sweep: &Sweep, index: usize) {
+    for vertex in profile {
+        let normal = vertex.normal.x * frame.normal + vertex.normal.y * frame.binormal;
+        let position = frame.origin + vertex.position.x * frame.normal + vertex.position.y * frame.binormal;
+        let uv = Vec2::new(index as f32 / sweep.length_segments as f32, vertex.v);
+
+        mesh.normals.push(normal.normalize_or_zero());
+        mesh.positions.push(position);
+        mesh.uvs.push(uv);
+    }
+}
+
+fn index_sweep(mesh: &mut MeshData, sweep: &Sweep, num_profile_vertices: u32) {
+    for ls in 0..sweep.length_segments {
+        for segment in 0..num_profile_vertices / 2 {
+            let lower = ls * num_profile_vertices + segment * 2;
+            let upper = lower + num_profile_vertices;
+            let indices = FlatTrapezeIndices {
+                lower_left: lower + 1,
+                upper_left: upper + 1,
+                lower_right: lower,
+                upper_right: upper,
+            };
+            indices.generate_triangles(&mut mesh.indices);
+        }
+    }
+}
+
+impl TryFrom<Sweep> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(sweep: Sweep) -> Result<Self, Self::Error> {
+
+        check(sweep.length_segments > 0, "length_segments", "must be at least 1")?;
+        if sweep.profile.closed {
+            check(sweep.profile.points.len() >= 3, "profile.points", "must contain at least 3 points for a closed profile")?;
+        }
+        else {
+            check(sweep.profile.points.len() >= 2, "profile.points", "must contain at least 2 points for an open profile")?;
+        }
+        check(sweep.profile.shading.is_empty() || sweep.profile.shading.len() == sweep.profile.points.len(), "profile.shading", "must be empty or contain one entry per point")?;
+        check(sweep.profile.points.iter().all(|p| p.is_finite()), "profile.points", "must be finite")?;
+        validate_points(&sweep.profile)?;
+
+        // Make closed profiles face outwards
+        let mut points = sweep.profile.points.clone();
+        let mut shading: Vec<EdgeShading> = (0..points.len()).map(|i| sweep.profile.shading_at(i)).collect();
+        if sweep.profile.closed {
+            let signed_area: f32 = (0..points.len()).map(|i| points[i].perp_dot(points[(i + 1) % points.len()])).sum();
+            if signed_area < 0.0 {
+                points.reverse();
+                shading.reverse();
+            }
+        }
+        let profile = profile_vertices(&sweep.profile, &points, &shading);
+
+        let num_vertices = (sweep.length_segments + 1) as usize * profile.len();
+        let num_indices = sweep.length_segments as usize * profile.len() / 2 * 6;
+        let mut mesh = MeshData::new(num_vertices, num_indices);
+
+        let mut frames = calculate_frames(sweep.curve.deref(), sweep.length_segments + 1, sweep.frame_algorithm, sweep.initial_normal);
+        if let Some(twist) = &sweep.twist {
+            let step = 1.0 / sweep.length_segments as f32;
+            let ts: Vec<f32> = (0..frames.len()).map(|idx| step * idx as f32).collect();
+            twist_frames(frames.as_mut_slice(), &ts, twist.deref());
+        }
+        if sweep.normalize {
+            normalize_frames(frames.as_mut_slice());
+        }
+        for (idx, frame) in frames.iter().enumerate() {
+            add_sweep_segment(&mut mesh, frame, &profile, &sweep, idx);
+        }
+        index_sweep(&mut mesh, &sweep, profile.len() as u32);
+
+        let mut m = Mesh::new(PrimitiveTopology::TriangleList);
+        m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
+        m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
+        m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
+        m.set_indices(Some(Indices::U32(mesh.indices)));
+        Ok(m)
+    }
+}
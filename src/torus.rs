@@ -2,6 +2,7 @@ use bevy::math::Vec3;
 use bevy::prelude::{Mesh, Vec2};
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use crate::MeshData;
+use crate::error::{check, ShapeError};
 use crate::util::FlatTrapezeIndices;
 
 pub struct Torus {
@@ -38,25 +39,24 @@ impl Default for Torus {
     }
 }
 
-impl From<Torus> for Mesh {
-    fn from(torus: Torus) -> Mesh {
+impl TryFrom<Torus> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(torus: Torus) -> Result<Mesh, Self::Error> {
 
         // Input parameter validation
-        assert!(torus.radius > 0.0, "The radii of a torus must be positive");
-        assert!(torus.tube_radius > 0.0, "The radii of a torus must be positive");
-        assert!(torus.radial_segments >= 3, "Must have at least 3 radial segments");
-        assert!(torus.tube_segments >= 3, "3 Must have at least 3 tube segments");
-        assert!(torus.radial_circumference > 0.0, "Radial circumference must be positive");
-        assert!(torus.tube_circumference > 0.0, "Tube circumference must be positive");
-        assert!(torus.radial_circumference <= std::f32::consts::TAU, "Radial circumference must not exceed 2pi radians");
-        assert!(torus.tube_circumference <= std::f32::consts::TAU, "Tube circumference must not exceed 2pi radians");
+        check(torus.radius > 0.0, "radius", "must be positive")?;
+        check(torus.tube_radius > 0.0, "tube_radius", "must be positive")?;
+        check(torus.radial_segments >= 3, "radial_segments", "must be at least 3")?;
+        check(torus.tube_segments >= 3, "tube_segments", "must be at least 3")?;
+        check(torus.radial_circumference > 0.0 && torus.radial_circumference <= std::f32::consts::TAU, "radial_circumference", "must be in (0, 2pi]")?;
+        check(torus.tube_circumference > 0.0 && torus.tube_circumference <= std::f32::consts::TAU, "tube_circumference", "must be in (0, 2pi]")?;
         if torus.radial_circumference < std::f32::consts::TAU {
-            assert!(torus.radial_offset >= 0.0, "Radial offset must be between 0 and 2pi");
-            assert!(torus.radial_offset <= std::f32::consts::TAU, "Radial offset must be between 0 and 2pi");
+            check(torus.radial_offset >= 0.0 && torus.radial_offset <= std::f32::consts::TAU, "radial_offset", "must be in [0, 2pi]")?;
         }
-        if torus.tube_radius < std::f32::consts::TAU {
-            assert!(torus.tube_radius >= 0.0, "Tube offset must be between 0 and 2pi");
-            assert!(torus.tube_radius <= std::f32::consts::TAU, "Tube offset must be between 0 and 2pi");
+        if torus.tube_circumference < std::f32::consts::TAU {
+            check(torus.tube_offset >= 0.0 && torus.tube_offset <= std::f32::consts::TAU, "tube_offset", "must be in [0, 2pi]")?;
         }
 
         let num_vertices = (torus.radial_segments + 1) * (torus.tube_segments + 1);
@@ -74,7 +74,7 @@ impl From<Torus> for Mesh {
         m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
         m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
         m.set_indices(Some(Indices::U32(mesh.indices)));
-        m
+        Ok(m)
     }
 }
 
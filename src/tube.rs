@@ -1,7 +1,8 @@
-use std::ops::{Deref, Sub};
-use bevy::prelude::{Mesh, Quat, Vec2, Vec3};
+use std::ops::Deref;
+use bevy::prelude::{Color, Mesh, Quat, Vec2, Vec3};
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use crate::MeshData;
+use crate::error::{check, ShapeError};
 use crate::util::{Extent, FlatTrapezeIndices};
 
 pub trait Curve {
@@ -21,12 +22,250 @@ pub trait Curve {
 
         (v1 - v0).normalize()
     }
+
+    /// Approximate the length of the curve.
+    /// By default it will add up the distances between many closely spaced points along the curve.
+    fn length(&self) -> f32 {
+        sampled_length(self)
+    }
+
+    /// Calculate the second derivative of the position with respect to t.
+    /// By default it will take three close points and use their differences.
+    fn second_derivative_at(&self, t: f32) -> Vec3 {
+        // Smaller steps drown in rounding errors
+        const DELTA: f32 = 0.005;
+
+        let t = t.clamp(DELTA, 1.0 - DELTA);
+        let p0 = self.eval_at(t - DELTA);
+        let p1 = self.eval_at(t);
+        let p2 = self.eval_at(t + DELTA);
+
+        (p2 - 2.0 * p1 + p0) / (DELTA * DELTA)
+    }
+
+    /// Calculate how sharply the curve bends at a specific point. This is the inverse of the radius of the bend.
+    fn curvature_at(&self, t: f32) -> f32 {
+        let velocity = velocity_at(self, t);
+        let speed = velocity.length();
+        if speed <= f32::EPSILON {
+            return 0.0;
+        }
+        velocity.cross(self.second_derivative_at(t)).length() / (speed * speed * speed)
+    }
+
+    /// Calculate how quickly the curve twists out of its plane of bending at a specific point.
+    /// Positive values twist like a right-handed helix.
+    fn torsion_at(&self, t: f32) -> f32 {
+        // The third derivative is even more sensitive to rounding errors, so it needs larger steps
+        const DELTA: f32 = 0.01;
+
+        // All derivatives are taken at the same point, moved inwards so the widest step stays on the curve
+        let s = t.clamp(2.0 * DELTA, 1.0 - 2.0 * DELTA);
+        let velocity = velocity_at(self, s);
+        let bend = velocity.cross(self.second_derivative_at(s));
+        if bend.length_squared() <= f32::EPSILON {
+            return 0.0;
+        }
+        let jerk = (self.eval_at(s + 2.0 * DELTA) - 2.0 * self.eval_at(s + DELTA)
+            + 2.0 * self.eval_at(s - DELTA) - self.eval_at(s - 2.0 * DELTA)) / (2.0 * DELTA * DELTA * DELTA);
+        bend.dot(jerk) / bend.length_squared()
+    }
+
+    /// Find the t of the point on the curve that is closest to the given point.
+    /// By default it will check many points along the curve and then refine the closest one.
+    fn closest_point(&self, point: Vec3) -> f32 {
+        const SAMPLES: u32 = 256;
+        const REFINEMENTS: u32 = 32;
+
+        let distance_at = |t: f32| self.eval_at(t).distance_squared(point);
+        let step = 1.0 / SAMPLES as f32;
+        let closest = (0..=SAMPLES)
+            .map(|i| step * i as f32)
+            .min_by(|a, b| distance_at(*a).total_cmp(&distance_at(*b)))
+            .unwrap(); // unwrap: The range is never empty
+
+        // Narrow down the range around the closest sample
+        let mut low = f32::max(closest - step, 0.0);
+        let mut high = f32::min(closest + step, 1.0);
+        for _ in 0..REFINEMENTS {
+            let a = low + (high - low) / 3.0;
+            let b = high - (high - low) / 3.0;
+            if distance_at(a) < distance_at(b) {
+                high = b;
+            }
+            else {
+                low = a;
+            }
+        }
+        (low + high) / 2.0
+    }
+
+    /// Whether the curve ends where it starts. The tolerance grows with the length of the curve to allow for rounding errors,
+    /// so moving the curve around doesn't change the result.
+    fn is_closed(&self) -> bool {
+        const TOLERANCE: f32 = 0.00001;
+
+        let start = self.eval_at(0.0);
+        let end = self.eval_at(1.0);
+        start.distance(end) <= TOLERANCE * f32::max(1.0, self.length())
+    }
+}
+
+// The first derivative of the position with respect to t, unlike the tangent it isn't normalized
+fn velocity_at<C: Curve + ?Sized>(curve: &C, t: f32) -> Vec3 {
+    const DELTA: f32 = 0.005;
+
+    let t = t.clamp(DELTA, 1.0 - DELTA);
+    (curve.eval_at(t + DELTA) - curve.eval_at(t - DELTA)) / (2.0 * DELTA)
+}
+
+impl<C: Curve + ?Sized> Curve for Box<C> {
+    fn eval_at(&self, t: f32) -> Vec3 {
+        self.deref().eval_at(t)
+    }
+
+    fn tangent_at(&self, t: f32) -> Vec3 {
+        self.deref().tangent_at(t)
+    }
+
+    fn length(&self) -> f32 {
+        self.deref().length()
+    }
+
+    fn second_derivative_at(&self, t: f32) -> Vec3 {
+        self.deref().second_derivative_at(t)
+    }
+
+    fn curvature_at(&self, t: f32) -> f32 {
+        self.deref().curvature_at(t)
+    }
+
+    fn torsion_at(&self, t: f32) -> f32 {
+        self.deref().torsion_at(t)
+    }
+
+    fn closest_point(&self, point: Vec3) -> f32 {
+        self.deref().closest_point(point)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.deref().is_closed()
+    }
+}
+
+// The default length of a curve, also used by curves that can only forward it in some cases
+pub(crate) fn sampled_length<C: Curve + ?Sized>(curve: &C) -> f32 {
+    const SAMPLES: u32 = 1024;
+
+    let mut length = 0.0;
+    let mut prev = curve.eval_at(0.0);
+    for i in 1..=SAMPLES {
+        let p = curve.eval_at(i as f32 / SAMPLES as f32);
+        length += p.distance(prev);
+        prev = p;
+    }
+    length
+}
+
+/// Describes how the radius of a tube changes along its curve.
+pub trait RadiusProfile {
+
+    /// The radius at some point along the curve. Like for the curve, t is in [0, 1].
+    fn radius_at(&self, t: f32) -> f32;
+
+    /// Calculate the rate of change of the radius with respect to t.
+    /// By default it will take two close points and use their difference. Both stay within [0, 1], so the difference is one-sided at the ends.
+    fn derivative_at(&self, t: f32) -> f32 {
+        const DELTA: f32 = 0.0001;
+
+        let t0 = f32::max(t - DELTA, 0.0);
+        let t1 = f32::min(t + DELTA, 1.0);
+        let r0 = self.radius_at(t0);
+        let r1 = self.radius_at(t1);
+
+        (r1 - r0) / (t1 - t0)
+    }
+}
+
+impl<F: Fn(f32) -> f32> RadiusProfile for F {
+    fn radius_at(&self, t: f32) -> f32 {
+        self(t)
+    }
+}
+
+/// Describes how a tube twists around its curve.
+pub trait TwistProfile {
+
+    /// The angle in radians the tube is rotated by around the tangent at some point along the curve. Like for the curve, t is in [0, 1].
+    fn twist_at(&self, t: f32) -> f32;
+}
+
+impl<F: Fn(f32) -> f32> TwistProfile for F {
+    fn twist_at(&self, t: f32) -> f32 {
+        self(t)
+    }
+}
+
+/// A plain number is the total twist from the start to the end of the curve, spread out evenly.
+impl TwistProfile for f32 {
+    fn twist_at(&self, t: f32) -> f32 {
+        self * t
+    }
+}
+
+/// Radii at specific points along the curve. The radius is interpolated linearly between them
+/// and stays constant before the first and after the last key.
+pub struct KeyedRadius {
+    /// Pairs of t and the radius at t. Must be sorted by t.
+    pub keys: Vec<(f32, f32)>,
+}
+
+impl KeyedRadius {
+    /// Create the profile from keys in any order.
+    pub fn new(mut keys: Vec<(f32, f32)>) -> Self {
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { keys }
+    }
+
+    // The index of the first key after t, clamped so there is always a key before it.
+    fn upper_key(&self, t: f32) -> usize {
+        self.keys.partition_point(|(key, _)| *key <= t).clamp(1, self.keys.len() - 1)
+    }
+}
+
+impl RadiusProfile for KeyedRadius {
+    fn radius_at(&self, t: f32) -> f32 {
+        match self.keys.as_slice() {
+            [] => 0.0,
+            [(_, radius)] => *radius,
+            _ => {
+                let (t0, r0) = self.keys[self.upper_key(t) - 1];
+                let (t1, r1) = self.keys[self.upper_key(t)];
+                let s = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+                r0 + (r1 - r0) * s
+            }
+        }
+    }
+
+    fn derivative_at(&self, t: f32) -> f32 {
+        if self.keys.len() < 2 {
+            return 0.0;
+        }
+        let (t0, r0) = self.keys[self.upper_key(t) - 1];
+        let (t1, r1) = self.keys[self.upper_key(t)];
+        if t < t0 || t > t1 || t1 <= t0 {
+            0.0
+        }
+        else {
+            (r1 - r0) / (t1 - t0)
+        }
+    }
 }
 
 /// Default curve implementation. It's a straight line up (y+).
 /// This is mainly used as a fallback and is thus not public.
 /// Users are expected to bring their own curve implementations.
-struct DefaultCurve;
+pub(crate) struct DefaultCurve;
 
 impl Curve for DefaultCurve {
     fn eval_at(&self, t: f32) -> Vec3 {
@@ -40,6 +279,123 @@ impl Curve for DefaultCurve {
     }
 }
 
+/// How the open ends of a tube are closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TubeCap {
+    /// Leave the end open.
+    None,
+    /// Close the end with a flat disc.
+    Flat,
+    /// Close the end with a hemisphere, made out of the given number of segments from its rim to its tip.
+    Dome(u32),
+}
+
+/// Settings for placing the length segments of a tube where the curve bends, instead of spreading them evenly.
+/// A segment is split in half until it follows the curve closely enough or the maximum number of segments is reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSegmentation {
+    /// The maximum angle in radians between the tangents at the start, middle and end of a segment.
+    pub max_angle: f32,
+    /// The maximum distance between the middle of a segment on the curve and the straight line approximating it.
+    /// Measured in the curve's units, before the curve is normalized.
+    pub max_chord_error: f32,
+    /// The number of even segments the curve is split into before subdividing. Prevents missing small features.
+    pub min_segments: u32,
+    /// The upper limit for the number of segments. The segments that deviate from the curve the most are split first.
+    pub max_segments: u32,
+}
+
+impl Default for AdaptiveSegmentation {
+    fn default() -> Self {
+        AdaptiveSegmentation {
+            max_angle: 0.1,
+            max_chord_error: 0.001,
+            min_segments: 4,
+            max_segments: 1024,
+        }
+    }
+}
+
+// How much a segment deviates from the curve, relative to the tolerances. Values above 1 need to be split.
+fn segment_error(curve: &dyn Curve, t0: f32, t1: f32, settings: &AdaptiveSegmentation) -> f32 {
+    let tm = (t0 + t1) / 2.0;
+    let p0 = curve.eval_at(t0);
+    let p1 = curve.eval_at(t1);
+    let pm = curve.eval_at(tm);
+    let tan0 = curve.tangent_at(t0);
+    let tanm = curve.tangent_at(tm);
+    let tan1 = curve.tangent_at(t1);
+
+    // Check both halves, the tangents at the ends alone can line up again after an S-bend
+    let angle = f32::max(tan0.angle_between(tanm), tanm.angle_between(tan1));
+    let chord = p1 - p0;
+    let chord_error = match chord.try_normalize() {
+        Some(direction) => (pm - p0).reject_from_normalized(direction).length(),
+        None => pm.distance(p0),
+    };
+
+    f32::max(angle / settings.max_angle, chord_error / settings.max_chord_error)
+}
+
+// The values of t that split the curve into segments that follow it within the tolerances
+fn adaptive_segments(curve: &dyn Curve, settings: &AdaptiveSegmentation) -> Vec<f32> {
+
+    // Sharp corners never get within the tolerances, stop splitting them at some point
+    const MIN_SEGMENT_LENGTH: f32 = 0.0001;
+
+    let mut ts: Vec<f32> = (0..=settings.min_segments)
+        .map(|i| i as f32 / settings.min_segments as f32)
+        .collect();
+
+    // Split all segments that are off by too much at once, the worst ones first when running into the limit
+    loop {
+        let budget = settings.max_segments as usize - (ts.len() - 1);
+        if budget == 0 {
+            break;
+        }
+        let mut split: Vec<(usize, f32)> = ts.windows(2)
+            .enumerate()
+            .filter(|(_, w)| w[1] - w[0] > MIN_SEGMENT_LENGTH)
+            .map(|(i, w)| (i, segment_error(curve, w[0], w[1], settings)))
+            .filter(|(_, error)| *error > 1.0)
+            .collect();
+        if split.is_empty() {
+            break;
+        }
+        split.sort_by(|a, b| b.1.total_cmp(&a.1));
+        split.truncate(budget);
+        split.sort_by_key(|(i, _)| *i);
+
+        let mut refined = Vec::with_capacity(ts.len() + split.len());
+        let mut next_split = split.iter().peekable();
+        for i in 0..ts.len() - 1 {
+            refined.push(ts[i]);
+            if next_split.next_if(|(j, _)| *j == i).is_some() {
+                refined.push((ts[i] + ts[i + 1]) / 2.0);
+            }
+        }
+        refined.push(ts[ts.len() - 1]);
+        ts = refined;
+    }
+
+    ts
+}
+
+/// How the orientation of the cross-section is chosen along a curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameAlgorithm {
+    /// Rotate the cross-section as little as possible from one point to the next. This avoids any unnecessary twist.
+    /// On closed curves, the remaining difference at the seam is spread out evenly along the curve.
+    ParallelTransport,
+    /// The true Frenet-Serret frame, its normal points towards the center of curvature.
+    /// This flips around at inflection points. Straight parts don't have a curvature, they use parallel transport instead.
+    /// On closed curves, that can leave a seam if the curve starts or ends on a straight part.
+    FrenetSerret,
+    /// Keep the binormal as close to this up vector as possible, like a road that never banks.
+    /// Where the curve runs parallel to the up vector, parallel transport is used instead, which can leave a seam on closed curves.
+    FixedUp(Vec3),
+}
+
 pub struct Tube {
     pub radius: f32,
     pub curve: Box<dyn Curve>,
@@ -47,6 +403,24 @@ pub struct Tube {
     pub radial_segments: u32,
     pub radial_circumference: f32,
     pub radial_offset: f32,
+    /// Whether the curve is recentered and scaled to fit into the unit box.
+    /// Disable this to keep the curve's coordinates, the radius is then measured in the curve's units as well.
+    pub normalize: bool,
+    /// Varies the radius along the curve. If this is set, the constant radius is ignored.
+    pub radius_profile: Option<Box<dyn RadiusProfile>>,
+    /// Closes the tube where the curve starts. Only applies to tubes with at least 3 radial segments.
+    pub start_cap: TubeCap,
+    /// Closes the tube where the curve ends. Only applies to tubes with at least 3 radial segments.
+    pub end_cap: TubeCap,
+    /// Places the length segments where the curve bends. If this is set, the number of length segments is ignored.
+    pub adaptive_segmentation: Option<AdaptiveSegmentation>,
+    /// How the tube is oriented along the curve. This decides where its seam and ribbons face.
+    pub frame_algorithm: FrameAlgorithm,
+    /// The normal at the start of the curve, for frame algorithms that don't determine it themselves.
+    /// If this is None, or parallel to the tangent, a direction perpendicular to the tangent is picked.
+    pub initial_normal: Option<Vec3>,
+    /// Rotates the tube around its curve on top of the frame algorithm. Positive angles follow the right-hand rule around the tangent.
+    pub twist: Option<Box<dyn TwistProfile>>,
 }
 
 impl Default for Tube {
@@ -58,18 +432,26 @@ impl Default for Tube {
             radial_segments: 64,
             radial_circumference: std::f32::consts::TAU,
             radial_offset: 0.0,
+            normalize: true,
+            radius_profile: None,
+            start_cap: TubeCap::None,
+            end_cap: TubeCap::None,
+            adaptive_segmentation: None,
+            frame_algorithm: FrameAlgorithm::ParallelTransport,
+            initial_normal: None,
+            twist: None,
         }
     }
 }
 
-struct FrenetSerretFrame {
-    origin: Vec3,
-    tangent: Vec3,
-    normal: Vec3,
-    binormal: Vec3,
+pub(crate) struct FrenetSerretFrame {
+    pub origin: Vec3,
+    pub tangent: Vec3,
+    pub normal: Vec3,
+    pub binormal: Vec3,
 }
 
-fn initial_normal(tangent: Vec3) -> Vec3 {
+fn guess_initial_normal(tangent: Vec3) -> Vec3 {
 
     // Select initial normal in the direction of the minimum component of the tangent
     let mut min = f32::MAX;
@@ -94,12 +476,33 @@ fn initial_normal(tangent: Vec3) -> Vec3 {
     normal
 }
 
-fn initial_frame(curve: &dyn Curve) -> FrenetSerretFrame {
+// The normal the frame algorithm dictates at this point, if any
+fn preferred_normal(curve: &dyn Curve, t: f32, tangent: Vec3, algorithm: FrameAlgorithm) -> Option<Vec3> {
+    match algorithm {
+        FrameAlgorithm::ParallelTransport => None,
+        FrameAlgorithm::FrenetSerret => {
+            // The tangent turns towards the center of curvature. The step is large enough to not pick up numerical noise on straight parts.
+            const DELTA: f32 = 0.01;
+            const MIN_TURN: f32 = 0.001;
+
+            let turn = curve.tangent_at(f32::min(t + DELTA, 1.0)) - curve.tangent_at(f32::max(t - DELTA, 0.0));
+            let normal = turn.reject_from_normalized(tangent);
+            if normal.length() > MIN_TURN { Some(normal.normalize()) } else { None }
+        }
+        FrameAlgorithm::FixedUp(up) => {
+            let binormal = up.reject_from_normalized(tangent).try_normalize()?;
+            Some(binormal.cross(tangent))
+        }
+    }
+}
+
+fn initial_frame(curve: &dyn Curve, algorithm: FrameAlgorithm, initial_normal: Option<Vec3>) -> FrenetSerretFrame {
 
     let origin = curve.eval_at(0.0);
     let tangent = curve.tangent_at(0.0);
-    let normal = initial_normal(tangent);
-    let v = tangent.cross(tangent.cross(normal).normalize());
+    let v = preferred_normal(curve, 0.0, tangent, algorithm)
+        .or_else(|| initial_normal.and_then(|normal| normal.reject_from_normalized(tangent).try_normalize()))
+        .unwrap_or_else(|| tangent.cross(tangent.cross(guess_initial_normal(tangent)).normalize()));
 
     FrenetSerretFrame {
         origin,
@@ -109,19 +512,24 @@ fn initial_frame(curve: &dyn Curve) -> FrenetSerretFrame {
     }
 }
 
-fn calculate_frames(curve: &dyn Curve, num_frames: u32) -> Vec<FrenetSerretFrame> {
-
-    let mut out = Vec::with_capacity(num_frames as usize);
+pub(crate) fn calculate_frames(curve: &dyn Curve, num_frames: u32, algorithm: FrameAlgorithm, initial_normal: Option<Vec3>) -> Vec<FrenetSerretFrame> {
     let step = 1.0 / (num_frames - 1) as f32;
-    
+    let ts: Vec<f32> = (0..num_frames).map(|i| step * i as f32).collect();
+    calculate_frames_at(curve, &ts, algorithm, initial_normal)
+}
+
+// Calculate frames at the given values of t. They must start at 0, end at 1 and be in ascending order.
+pub(crate) fn calculate_frames_at(curve: &dyn Curve, ts: &[f32], algorithm: FrameAlgorithm, initial_normal: Option<Vec3>) -> Vec<FrenetSerretFrame> {
+
+    let mut out = Vec::with_capacity(ts.len());
+
     // First frame is different
-    out.push(initial_frame(curve));
+    out.push(initial_frame(curve, algorithm, initial_normal));
 
     // Calculate a smoothly shifting coordinate frame for each segment point
-    for i in 1..num_frames {
+    for (i, t) in ts.iter().copied().enumerate().skip(1) {
 
-        let t = step * i as f32;
-        let prev_frame: &FrenetSerretFrame = out.get(i as usize - 1).unwrap(); // unwrap: i starts at 1
+        let prev_frame: &FrenetSerretFrame = out.get(i - 1).unwrap(); // unwrap: i starts at 1
 
         let mut cur_frame = FrenetSerretFrame {
             origin: curve.eval_at(t),
@@ -139,24 +547,27 @@ fn calculate_frames(curve: &dyn Curve, num_frames: u32) -> Vec<FrenetSerretFrame
             let rot = Quat::from_axis_angle(v, theta);
             cur_frame.normal = rot.mul_vec3(cur_frame.normal);
         }
+        if let Some(normal) = preferred_normal(curve, t, cur_frame.tangent, algorithm) {
+            cur_frame.normal = normal;
+        }
 
         cur_frame.binormal = cur_frame.tangent.cross(cur_frame.normal);
 
         out.push(cur_frame);
     }
 
-    // If the curve is closed, make the frames line up
-    let start_end_distance = curve.eval_at(0.0).sub(curve.eval_at(1.0)).length();
-    if start_end_distance <= 2.0 * f32::EPSILON {
+    // If the curve is closed, make the frames line up. The other algorithms take their normals from the curve,
+    // except where they fall back to parallel transport. A seam can remain there, correcting it would rotate the frames they do determine.
+    if algorithm == FrameAlgorithm::ParallelTransport && curve.is_closed() {
 
-        let first_frame = out.get(0).unwrap(); // unwrap: We have >= 1 segment
+        let first_frame = out.first().unwrap(); // unwrap: We have >= 1 segment
         let last_frame = out.last().unwrap(); // unwrap: We have >= 1 segment
 
         // Post-process the frames
         let discrepancy_theta = {
             let t = first_frame.normal.dot(last_frame.normal)
                 .clamp(-1.0, 1.0)
-                .acos() / (num_frames - 1) as f32;
+                .acos();
             if first_frame.tangent.dot(first_frame.normal.cross(last_frame.normal)) > 0.0 {
                 -t
             }
@@ -165,9 +576,9 @@ fn calculate_frames(curve: &dyn Curve, num_frames: u32) -> Vec<FrenetSerretFrame
             }
         };
 
-        // Rotate each frame a little to make them line up
-        for (idx, frame) in out.iter_mut().skip(1).enumerate() {
-            let rot = Quat::from_axis_angle(frame.tangent, discrepancy_theta * idx as f32);
+        // Rotate each frame a little to make them line up, spread evenly over the length of the curve
+        for (frame, t) in out.iter_mut().zip(ts).skip(1) {
+            let rot = Quat::from_axis_angle(frame.tangent, discrepancy_theta * t);
             frame.normal = rot.mul_vec3(frame.normal);
             frame.binormal = frame.tangent.cross(frame.normal);
         }
@@ -176,7 +587,16 @@ fn calculate_frames(curve: &dyn Curve, num_frames: u32) -> Vec<FrenetSerretFrame
     out
 }
 
-fn normalize_frames(frames: &mut [FrenetSerretFrame]) {
+// Rotate each frame around its tangent by the twist at its point on the curve
+pub(crate) fn twist_frames(frames: &mut [FrenetSerretFrame], ts: &[f32], twist: &dyn TwistProfile) {
+    for (frame, t) in frames.iter_mut().zip(ts) {
+        let rot = Quat::from_axis_angle(frame.tangent, twist.twist_at(*t));
+        frame.normal = rot.mul_vec3(frame.normal);
+        frame.binormal = frame.tangent.cross(frame.normal);
+    }
+}
+
+pub(crate) fn normalize_frames(frames: &mut [FrenetSerretFrame]) {
     let mut extent = Extent::new();
     for frame in frames.iter() {
         extent.extend_to_include(frame.origin);
@@ -191,7 +611,8 @@ fn normalize_frames(frames: &mut [FrenetSerretFrame]) {
     }
 }
 
-fn add_tube_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, index: usize) {
+// The slope is the change of radius per distance travelled along the curve
+fn add_tube_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, t: f32, radius: f32, slope: f32) {
 
     let angle_step = tube.radial_circumference / tube.radial_segments as f32;
 
@@ -200,10 +621,12 @@ fn add_tube_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube,
         let sin = theta.sin();
         let cos = -theta.cos();
 
-        let normal = Vec3::normalize(cos * frame.normal + sin * frame.binormal);
-        let position = frame.origin + tube.radius * normal;
+        // Tilt the normal against the direction the tube widens in, just like on a cone
+        let radial = Vec3::normalize(cos * frame.normal + sin * frame.binormal);
+        let normal = Vec3::normalize(radial - slope * frame.tangent);
+        let position = frame.origin + radius * radial;
         let uv = Vec2::new(
-            index as f32 / tube.length_segments as f32,
+            t,
             i as f32 / tube.radial_segments as f32
         );
 
@@ -213,7 +636,7 @@ fn add_tube_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube,
     }
 }
 
-fn add_ribbon_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, index: usize) {
+fn add_ribbon_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, t: f32, radius: f32) {
 
     let theta = tube.radial_offset + std::f32::consts::FRAC_PI_2;
     let sin = theta.sin();
@@ -224,14 +647,14 @@ fn add_ribbon_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tub
     let front_normal = frame.tangent.cross(base);
     mesh.normals.push(front_normal);
     mesh.normals.push(front_normal);
-    mesh.positions.push(frame.origin + tube.radius * base);
-    mesh.positions.push(frame.origin + tube.radius * -base);
+    mesh.positions.push(frame.origin + radius * base);
+    mesh.positions.push(frame.origin + radius * -base);
     mesh.uvs.push(Vec2::new(
-        index as f32 / tube.length_segments as f32,
+        t,
         0.0
     ));
     mesh.uvs.push(Vec2::new(
-        index as f32 / tube.length_segments as f32,
+        t,
         1.0
     ));
 
@@ -239,19 +662,98 @@ fn add_ribbon_segment(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tub
     if tube.radial_segments == 2 {
         mesh.normals.push(-front_normal);
         mesh.normals.push(-front_normal);
-        mesh.positions.push(frame.origin + tube.radius * -base);
-        mesh.positions.push(frame.origin + tube.radius * base);
+        mesh.positions.push(frame.origin + radius * -base);
+        mesh.positions.push(frame.origin + radius * base);
         mesh.uvs.push(Vec2::new(
-            index as f32 / tube.length_segments as f32,
+            t,
             0.0
         ));
         mesh.uvs.push(Vec2::new(
-            index as f32 / tube.length_segments as f32,
+            t,
             1.0
         ));
     }
 }
 
+// The direction is -1 for the start of the tube and 1 for its end
+fn add_flat_cap(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, radius: f32, direction: f32) {
+
+    let angle_step = tube.radial_circumference / tube.radial_segments as f32;
+    let normal = direction * frame.tangent;
+    let base_index = mesh.positions.len() as u32;
+
+    // Center
+    mesh.positions.push(frame.origin);
+    mesh.normals.push(normal);
+    mesh.uvs.push(Vec2::new(0.5, 0.5));
+
+    // Rim
+    for i in 0..=tube.radial_segments {
+        let theta = angle_step * i as f32 + tube.radial_offset;
+        let sin = theta.sin();
+        let cos = -theta.cos();
+
+        let radial = Vec3::normalize(cos * frame.normal + sin * frame.binormal);
+        mesh.positions.push(frame.origin + radius * radial);
+        mesh.normals.push(normal);
+        mesh.uvs.push(Vec2::new(cos * 0.5 + 0.5, sin * direction * 0.5 + 0.5));
+    }
+
+    // Indices
+    for i in 0..tube.radial_segments {
+        if direction > 0.0 {
+            mesh.indices.push(base_index + i + 2);
+            mesh.indices.push(base_index + i + 1);
+            mesh.indices.push(base_index);
+        }
+        else {
+            mesh.indices.push(base_index);
+            mesh.indices.push(base_index + i + 1);
+            mesh.indices.push(base_index + i + 2);
+        }
+    }
+}
+
+// The direction is -1 for the start of the tube and 1 for its end
+fn add_dome_cap(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, radius: f32, direction: f32, segments: u32) {
+
+    let angle_step = tube.radial_circumference / tube.radial_segments as f32;
+    let base_index = mesh.positions.len() as u32;
+
+    // Add the rings in the direction of the curve, so they can be indexed like the tube itself
+    for ring in 0..=segments {
+        let k = if direction > 0.0 { ring } else { segments - ring };
+        let phi = k as f32 / segments as f32 * std::f32::consts::FRAC_PI_2;
+        // Make sure the tip is exactly on the curve
+        let (sin_phi, cos_phi) = if k == segments { (1.0, 0.0) } else { phi.sin_cos() };
+
+        for i in 0..=tube.radial_segments {
+            let theta = angle_step * i as f32 + tube.radial_offset;
+            let sin = theta.sin();
+            let cos = -theta.cos();
+
+            let radial = Vec3::normalize(cos * frame.normal + sin * frame.binormal);
+            let normal = cos_phi * radial + sin_phi * direction * frame.tangent;
+            mesh.positions.push(frame.origin + radius * normal);
+            mesh.normals.push(normal);
+            mesh.uvs.push(Vec2::new(
+                ring as f32 / segments as f32,
+                i as f32 / tube.radial_segments as f32,
+            ));
+        }
+    }
+
+    index_rings(mesh, base_index, segments, tube.radial_segments);
+}
+
+fn add_cap(mesh: &mut MeshData, frame: &FrenetSerretFrame, tube: &Tube, radius: f32, direction: f32, cap: TubeCap) {
+    match cap {
+        TubeCap::None => {}
+        TubeCap::Flat => add_flat_cap(mesh, frame, tube, radius, direction),
+        TubeCap::Dome(segments) => add_dome_cap(mesh, frame, tube, radius, direction, segments),
+    }
+}
+
 // Calculate the bounding box of this mesh and then shrink the mesh to fit into the unit box
 fn normalize_positions(positions: &mut [Vec3]) {
 
@@ -269,14 +771,19 @@ fn normalize_positions(positions: &mut [Vec3]) {
     }
 }
 
-fn index_tube(mesh: &mut MeshData, tube: &Tube) {
-    for j in 1..=tube.length_segments {
-        for i in 1..=tube.radial_segments {
+fn index_tube(mesh: &mut MeshData, tube: &Tube, base_index: u32, length_segments: u32) {
+    index_rings(mesh, base_index, length_segments, tube.radial_segments);
+}
+
+// Index consecutive rings of vertices that follow the direction of the curve
+fn index_rings(mesh: &mut MeshData, base_index: u32, length_segments: u32, radial_segments: u32) {
+    for j in 1..=length_segments {
+        for i in 1..=radial_segments {
 
-            let a = ( tube.radial_segments + 1 ) * ( j - 1 ) + ( i - 1 );
-            let b = ( tube.radial_segments + 1 ) * j + ( i - 1 );
-            let c = ( tube.radial_segments + 1 ) * j + i;
-            let d = ( tube.radial_segments + 1 ) * ( j - 1 ) + i;
+            let a = base_index + ( radial_segments + 1 ) * ( j - 1 ) + ( i - 1 );
+            let b = base_index + ( radial_segments + 1 ) * j + ( i - 1 );
+            let c = base_index + ( radial_segments + 1 ) * j + i;
+            let d = base_index + ( radial_segments + 1 ) * ( j - 1 ) + i;
 
             // faces
             mesh.indices.push(a);
@@ -289,14 +796,14 @@ fn index_tube(mesh: &mut MeshData, tube: &Tube) {
     }
 }
 
-fn index_ribbon(mesh: &mut MeshData, tube: &Tube) {
-    for ls in 0..tube.length_segments {
+fn index_ribbon(mesh: &mut MeshData, tube: &Tube, base_index: u32, length_segments: u32) {
+    for ls in 0..length_segments {
         for rs in 0..tube.radial_segments {
             let indices = FlatTrapezeIndices {
-                lower_left: 2 * tube.radial_segments * ls + 2 * rs,
-                upper_left: 2 * tube.radial_segments * (ls + 1) + 2 * rs,
-                lower_right: 2 * tube.radial_segments * ls + 2 * rs + 1,
-                upper_right: 2 * tube.radial_segments * (ls + 1) + 2 * rs + 1,
+                lower_left: base_index + 2 * tube.radial_segments * ls + 2 * rs + 1,
+                upper_left: base_index + 2 * tube.radial_segments * (ls + 1) + 2 * rs + 1,
+                lower_right: base_index + 2 * tube.radial_segments * ls + 2 * rs,
+                upper_right: base_index + 2 * tube.radial_segments * (ls + 1) + 2 * rs,
             };
             indices.generate_triangles(&mut mesh.indices);
         }
@@ -305,65 +812,228 @@ fn index_ribbon(mesh: &mut MeshData, tube: &Tube) {
 
 // The implementation of this algorithm is based on three.js.
 // https://github.com/mrdoob/three.js
-fn add_tube(mesh: &mut MeshData, tube: &Tube) {
+// The tube is appended to the mesh, so several of them can share one
+pub(crate) fn add_tube(mesh: &mut MeshData, tube: &Tube) -> Result<(), ShapeError> {
+    let (ts, frames) = tube_frames(tube);
+    add_tube_on_frames(mesh, tube, &ts, &frames)
+}
+
+// Like add_tube, but the rings are placed on the given frames. The tube's curve and its segmentation are ignored.
+pub(crate) fn add_tube_on_frames(mesh: &mut MeshData, tube: &Tube, ts: &[f32], frames: &[FrenetSerretFrame]) -> Result<(), ShapeError> {
+
+    let base_index = mesh.positions.len() as u32;
+
+    let radii: Vec<f32> = match &tube.radius_profile {
+        Some(profile) => ts.iter().map(|t| profile.radius_at(*t)).collect(),
+        None => vec![tube.radius; frames.len()],
+    };
+    if tube.radius_profile.is_some() {
+        check(radii.iter().all(|r| r.is_finite() && *r >= 0.0), "radius_profile", "must be finite and not negative")?;
+    }
 
-    let mut frames = calculate_frames(tube.curve.deref(), tube.length_segments + 1);
-    normalize_frames(frames.as_mut_slice());
     for (idx, frame) in frames.iter().enumerate() {
         if tube.radial_segments < 3 {
-            add_ribbon_segment(mesh, frame, tube, idx);
+            add_ribbon_segment(mesh, frame, tube, ts[idx], radii[idx]);
         }
         else {
-            add_tube_segment(mesh, frame, tube, idx);
+            // The radius changes with t, but the normals need its change over the distance travelled along the curve.
+            // The frames are already scaled, so we measure that distance between them.
+            let slope = match &tube.radius_profile {
+                Some(profile) => {
+                    let prev = idx.saturating_sub(1);
+                    let next = usize::min(idx + 1, frames.len() - 1);
+                    let speed = frames[next].origin.distance(frames[prev].origin) / (ts[next] - ts[prev]);
+                    if speed > f32::EPSILON { profile.derivative_at(ts[idx]) / speed } else { 0.0 }
+                }
+                None => 0.0,
+            };
+            check(slope.is_finite(), "radius_profile", "must have a finite slope")?;
+            add_tube_segment(mesh, frame, tube, ts[idx], radii[idx], slope);
         }
     }
 
     // Generate indices for the faces
+    let length_segments = ts.len() as u32 - 1;
     if tube.radial_segments < 3 {
-        index_ribbon(mesh, tube);
+        index_ribbon(mesh, tube, base_index, length_segments);
     }
     else {
-        index_tube(mesh, tube);
+        index_tube(mesh, tube, base_index, length_segments);
+        add_cap(mesh, &frames[0], tube, radii[0], -1.0, tube.start_cap);
+        add_cap(mesh, &frames[frames.len() - 1], tube, radii[frames.len() - 1], 1.0, tube.end_cap);
     }
+
+    Ok(())
+}
+
+// The values of t at the start of each length segment and the end of the last one
+fn segment_ts(tube: &Tube) -> Vec<f32> {
+    match &tube.adaptive_segmentation {
+        Some(settings) => adaptive_segments(tube.curve.deref(), settings),
+        None => {
+            let step = 1.0 / tube.length_segments as f32;
+            (0..=tube.length_segments).map(|i| step * i as f32).collect()
+        }
+    }
+}
+
+// The frames the rings of the tube are placed on, along with their t. Shared with the curve follower.
+pub(crate) fn tube_frames(tube: &Tube) -> (Vec<f32>, Vec<FrenetSerretFrame>) {
+    let ts = segment_ts(tube);
+    let mut frames = calculate_frames_at(tube.curve.deref(), &ts, tube.frame_algorithm, tube.initial_normal);
+    if let Some(twist) = &tube.twist {
+        twist_frames(frames.as_mut_slice(), &ts, twist.deref());
+    }
+    if tube.normalize {
+        normalize_frames(frames.as_mut_slice());
+    }
+    (ts, frames)
 }
 
 fn make_line(tube: &Tube) -> Mesh {
     let mut m = Mesh::new(PrimitiveTopology::LineStrip);
-    let mut positions = Vec::with_capacity(tube.length_segments as usize + 1);
-    let step = 1.0 / tube.length_segments as f32;
-    for i in 0..=tube.length_segments {
-        let t = step * i as f32;
-        let p = tube.curve.eval_at(t);
-        positions.push(p);
-    }
-    normalize_positions(positions.as_mut_slice());
+    let mut positions: Vec<Vec3> = segment_ts(tube).into_iter()
+        .map(|t| tube.curve.eval_at(t))
+        .collect();
+    if tube.normalize {
+        normalize_positions(positions.as_mut_slice());
+    }
     m.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     m
 }
 
-impl From<Tube> for Mesh {
-    fn from(tube: Tube) -> Self {
+// Input parameter validation, shared with the gizmo and the cable
+pub(crate) fn validate(tube: &Tube) -> Result<(), ShapeError> {
+    check(tube.length_segments > 0, "length_segments", "must be at least 1")?;
+    check(tube.radial_offset >= 0.0 && tube.radial_offset <= std::f32::consts::TAU, "radial_offset", "must be in [0, 2pi]")?;
+    for (field, cap) in [("start_cap", tube.start_cap), ("end_cap", tube.end_cap)] {
+        if let TubeCap::Dome(segments) = cap {
+            check(segments >= 1, field, "must have at least 1 dome segment")?;
+        }
+    }
+    check(tube.radial_circumference > 0.0 && tube.radial_circumference <= std::f32::consts::TAU, "radial_circumference", "must be in (0, 2pi]")?;
+    if let Some(settings) = &tube.adaptive_segmentation {
+        check(settings.max_angle > 0.0, "adaptive_segmentation.max_angle", "must be positive")?;
+        check(settings.max_chord_error > 0.0, "adaptive_segmentation.max_chord_error", "must be positive")?;
+        check(settings.min_segments >= 1, "adaptive_segmentation.min_segments", "must be at least 1")?;
+        check(settings.max_segments >= settings.min_segments, "adaptive_segmentation.max_segments", "must not be less than min_segments")?;
+    }
+    Ok(())
+}
+
+impl TryFrom<Tube> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(tube: Tube) -> Result<Self, Self::Error> {
 
-        assert!(tube.length_segments > 0, "Must have at least one length segment");
-        assert!(tube.radial_offset >= 0.0 && tube.radial_offset <= std::f32::consts::TAU, "Radial offset must be in [0, 2pi]");
-        assert!(tube.radial_circumference > 0.0 && tube.radial_circumference <= std::f32::consts::TAU, "Radial circumference must be in (0, 2pi]");
+        validate(&tube)?;
 
         // Special case: Tube should be a line
-        if tube.radius.abs() < f32::EPSILON || tube.radial_segments == 0 {
-            return make_line(&tube);
+        if (tube.radius_profile.is_none() && tube.radius.abs() < f32::EPSILON) || tube.radial_segments == 0 {
+            return Ok(make_line(&tube));
         }
 
         let num_vertices = (tube.length_segments + 1) as usize * (tube.radial_segments + 1) as usize;
         let num_indices = tube.length_segments as usize * tube.radial_segments as usize * 6;
         let mut mesh = MeshData::new(num_vertices, num_indices);
 
-        add_tube(&mut mesh, &tube);
+        add_tube(&mut mesh, &tube)?;
 
         let mut m = Mesh::new(PrimitiveTopology::TriangleList);
         m.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.positions);
         m.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals);
         m.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.uvs);
         m.set_indices(Some(Indices::U32(mesh.indices)));
-        m
+        Ok(m)
+    }
+}
+
+/// A debug view of a tube. Draws its curve as a white line and the frames its rings are placed on
+/// as short red tangent, green normal and blue binormal lines. This shows where a tube twists or where its seam is off.
+pub struct FrameGizmo<'a> {
+    pub tube: &'a Tube,
+    /// The length of the lines drawn for each frame, in the same units as the tube's radius.
+    pub axis_length: f32,
+}
+
+impl TryFrom<FrameGizmo<'_>> for Mesh {
+
+    type Error = ShapeError;
+
+    fn try_from(gizmo: FrameGizmo) -> Result<Self, Self::Error> {
+
+        validate(gizmo.tube)?;
+        check(gizmo.axis_length >= 0.0, "axis_length", "must not be negative")?;
+
+        let (_, frames) = tube_frames(gizmo.tube);
+        let num_vertices = (frames.len() - 1) * 2 + frames.len() * 6;
+        let mut positions: Vec<Vec3> = Vec::with_capacity(num_vertices);
+        let mut colors: Vec<[f32; 4]> = Vec::with_capacity(num_vertices);
+
+        // Curve
+        for pair in frames.windows(2) {
+            positions.push(pair[0].origin);
+            positions.push(pair[1].origin);
+            colors.extend([Color::WHITE.as_rgba_f32(); 2]);
+        }
+
+        // Frames
+        for frame in &frames {
+            for (axis, color) in [(frame.tangent, Color::RED), (frame.normal, Color::GREEN), (frame.binormal, Color::BLUE)] {
+                positions.push(frame.origin);
+                positions.push(frame.origin + gizmo.axis_length * axis);
+                colors.extend([color.as_rgba_f32(); 2]);
+            }
+        }
+
+        let mut m = Mesh::new(PrimitiveTopology::LineList);
+        m.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        m.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Mesh;
+    use crate::curves::Helix;
+    use crate::error::ShapeError;
+    use super::{RadiusProfile, Tube};
+
+    #[test]
+    fn radius_slope_stays_within_the_curve() {
+        let taper = |t: f32| 0.1 * (1.0 - t).sqrt();
+        assert!(taper.derivative_at(0.0).is_finite());
+        assert!(taper.derivative_at(1.0).is_finite());
+
+        let mesh = Mesh::try_from(Tube {
+            curve: Box::new(Helix { radius: 1.0, height: 2.0, turns: 2.0 }),
+            radius_profile: Some(Box::new(taper)),
+            ..Default::default()
+        }).unwrap();
+        let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).unwrap().as_float3().unwrap();
+        assert!(normals.iter().flatten().all(|c| c.is_finite()));
+    }
+
+    #[test]
+    fn infinite_radius_slope_is_rejected() {
+        // The slope of a step can't be measured, the radius itself is fine everywhere
+        struct Step;
+        impl RadiusProfile for Step {
+            fn radius_at(&self, t: f32) -> f32 {
+                if t < 0.5 { 0.1 } else { 0.05 }
+            }
+
+            fn derivative_at(&self, t: f32) -> f32 {
+                if t == 0.5 { f32::NEG_INFINITY } else { 0.0 }
+            }
+        }
+        let result = Mesh::try_from(Tube {
+            radius_profile: Some(Box::new(Step)),
+            length_segments: 4,
+            ..Default::default()
+        });
+        assert_eq!(result.unwrap_err(), ShapeError::InvalidParameter { field: "radius_profile", constraint: "must have a finite slope" });
     }
 }
\ No newline at end of file